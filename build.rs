@@ -10,6 +10,10 @@ pub fn main() {
     println!("cargo::rerun-if-changed=src/file_dialog.h");
     println!("cargo::rerun-if-changed=src/chrono.cpp");
     println!("cargo::rerun-if-changed=src/chrono.h");
+    println!("cargo::rerun-if-changed=js_promise.cpp");
+    println!("cargo::rerun-if-changed=js_promise.h");
+    println!("cargo::rerun-if-changed=idle.cpp");
+    println!("cargo::rerun-if-changed=idle.h");
 
     let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("Could not find emsdk path"));
     let emsdk = PathBuf::from(std::env::var_os("EMSDK").expect("Could not find emsdk path"));
@@ -21,6 +25,8 @@ pub fn main() {
     std::thread::scope(|s| {
         build_bindings(s, &include, &sysroot, &out_dir);
         build_file_dialog(s, &sysroot, &out_dir);
+        build_js_promise(s, &sysroot, &out_dir);
+        build_idle(s, &sysroot, &out_dir);
 
         if std::env::var_os("CARGO_FEATURE_CHRONO").is_some() {
             build_chrono(s, &sysroot, &out_dir);
@@ -58,8 +64,9 @@ fn build_bindings<'scope, 'env>(
         }
 
         if std::env::var_os("CARGO_FEATURE_PROXYING").is_some() {
-            em_builder =
-                em_builder.header(include.join("emscripten/proxying.h").display().to_string())
+            em_builder = em_builder
+                .header(include.join("emscripten/proxying.h").display().to_string())
+                .header(include.join("emscripten/threading.h").display().to_string())
         }
 
         if std::env::var_os("CARGO_FEATURE_CONSOLE").is_some() {
@@ -99,8 +106,9 @@ fn build_bindings<'scope, 'env>(
         }
 
         if std::env::var_os("CARGO_FEATURE_PROXYING").is_some() {
-            em_builder =
-                em_builder.header(include.join("emscripten/proxying.h").display().to_string())
+            em_builder = em_builder
+                .header(include.join("emscripten/proxying.h").display().to_string())
+                .header(include.join("emscripten/threading.h").display().to_string())
         }
 
         if std::env::var_os("CARGO_FEATURE_CONSOLE").is_some() {
@@ -160,6 +168,72 @@ fn build_file_dialog<'scope, 'env>(
     });
 }
 
+fn build_js_promise<'scope, 'env>(
+    s: &'scope Scope<'scope, 'env>,
+    sysroot: &'env Path,
+    out_dir: &'env Path,
+) {
+    // TYPES
+    s.spawn(|| {
+        builder()
+            .header("js_promise.h")
+            .clang_arg(format!("--sysroot={}", sysroot.display()))
+            .clang_arg("-fvisibility=default")
+            .clang_arg("--target=wasm32-emscripten")
+            .default_enum_style(bindgen::EnumVariation::Rust {
+                non_exhaustive: true,
+            })
+            .generate_cstr(true)
+            .layout_tests(false)
+            .generate()
+            .unwrap()
+            .write_to_file(out_dir.join("js_promise.rs"))
+            .unwrap();
+    });
+
+    // COMPILE
+    s.spawn(|| {
+        cc::Build::new()
+            .file("js_promise.cpp")
+            .flag("-fvisibility=default")
+            .flag(format!("--sysroot={}", sysroot.display()))
+            .compile("js_promise");
+    });
+}
+
+fn build_idle<'scope, 'env>(
+    s: &'scope Scope<'scope, 'env>,
+    sysroot: &'env Path,
+    out_dir: &'env Path,
+) {
+    // TYPES
+    s.spawn(|| {
+        builder()
+            .header("idle.h")
+            .clang_arg(format!("--sysroot={}", sysroot.display()))
+            .clang_arg("-fvisibility=default")
+            .clang_arg("--target=wasm32-emscripten")
+            .default_enum_style(bindgen::EnumVariation::Rust {
+                non_exhaustive: true,
+            })
+            .generate_cstr(true)
+            .layout_tests(false)
+            .generate()
+            .unwrap()
+            .write_to_file(out_dir.join("idle.rs"))
+            .unwrap();
+    });
+
+    // COMPILE
+    s.spawn(|| {
+        cc::Build::new()
+            .file("idle.cpp")
+            .flag("-fvisibility=default")
+            .flag(format!("--sysroot={}", sysroot.display()))
+            .compile("idle");
+    });
+}
+
 fn build_chrono<'scope, 'env>(
     s: &'scope Scope<'scope, 'env>,
     sysroot: &'env Path,
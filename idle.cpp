@@ -0,0 +1,38 @@
+#include "idle.h"
+#include <emscripten.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void RequestIdle(RequestIdle_callback_t callback, void *callback_userdata) {
+  EM_ASM(
+      {
+        function onidle(timeRemaining) {
+          Module.ccall("__INTERNAL_IDLE_CALLBACK", "void",
+                       [ "number", "number", "number" ],
+                       [ $0, timeRemaining, $1 ]);
+        }
+
+        if (typeof requestIdleCallback === "function") {
+          requestIdleCallback(function(deadline) {
+            onidle(deadline.timeRemaining());
+          });
+        } else {
+          // Workers (and some browsers) don't have idle callbacks, so we
+          // give every timeout the maximum idle period instead
+          setTimeout(function() { onidle(50); }, 1);
+        }
+      },
+      callback, callback_userdata);
+}
+
+EMSCRIPTEN_KEEPALIVE void __INTERNAL_IDLE_CALLBACK(RequestIdle_callback_t cb,
+                                                   double time_remaining,
+                                                   void *user_data) {
+  (cb)(time_remaining, user_data);
+}
+
+#ifdef __cplusplus
+}
+#endif
@@ -0,0 +1,13 @@
+#pragma once
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (*RequestIdle_callback_t)(double, void *);
+
+void RequestIdle(RequestIdle_callback_t callback, void *callback_userdata);
+
+#ifdef __cplusplus
+}
+#endif
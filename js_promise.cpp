@@ -0,0 +1,56 @@
+#include "js_promise.h"
+#include <emscripten.h>
+#include <emscripten/val.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void PromiseThen(void *promise, PromiseThen_onsettled_t onsettled,
+                 void *onsettled_userdata) {
+  EM_ASM(
+      {
+        Promise.resolve(Emval.toValue($0))
+            .then(
+                function(value) {
+                  Module.ccall("__INTERNAL_PROMISE_ONSETTLED", "void",
+                               [ "number", "number", "number", "number" ],
+                               [ $1, Emval.toHandle(value), 1, $2 ]);
+                },
+                function(error) {
+                  Module.ccall("__INTERNAL_PROMISE_ONSETTLED", "void",
+                               [ "number", "number", "number", "number" ],
+                               [ $1, Emval.toHandle(error), 0, $2 ]);
+                });
+      },
+      promise, onsettled, onsettled_userdata);
+}
+
+void *PromiseWithResolvers(void **resolve, void **reject) {
+  return (void *)EM_ASM_PTR(
+      {
+        let resolve, reject;
+        const promise = new Promise(function(res, rej) {
+          resolve = res;
+          reject = rej;
+        });
+        Module.HEAPU32[$0 >> 2] = Emval.toHandle(resolve);
+        Module.HEAPU32[$1 >> 2] = Emval.toHandle(reject);
+        return Emval.toHandle(promise);
+      },
+      resolve, reject);
+}
+
+void PromiseSettle(void *settle, void *value) {
+  EM_ASM({ Emval.toValue($0)(Emval.toValue($1)); }, settle, value);
+}
+
+EMSCRIPTEN_KEEPALIVE void
+__INTERNAL_PROMISE_ONSETTLED(PromiseThen_onsettled_t cb, void *value,
+                             bool fulfilled, void *user_data) {
+  (cb)(value, fulfilled, user_data);
+}
+
+#ifdef __cplusplus
+}
+#endif
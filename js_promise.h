@@ -0,0 +1,17 @@
+#pragma once
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (*PromiseThen_onsettled_t)(void *, bool, void *);
+
+void PromiseThen(void *promise, PromiseThen_onsettled_t onsettled,
+                 void *onsettled_userdata);
+void *PromiseWithResolvers(void **resolve, void **reject);
+void PromiseSettle(void *settle, void *value);
+
+#ifdef __cplusplus
+}
+#endif
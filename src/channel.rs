@@ -0,0 +1,37 @@
+//! Asynchronous channels that can be shared between threads.
+//!
+//! Receivers are always woken on the thread they were polled from, proxying the wakeup through
+//! [`proxying`](crate::proxying) when the sender lives on a different thread. Receiving never blocks
+//! the thread, so it's safe to do from the browser's main thread.
+
+use crate::proxying::thread_waker;
+use futures::task::AtomicWaker;
+use std::task::Context;
+
+pub mod broadcast;
+pub mod mpsc;
+pub mod oneshot;
+pub mod watch;
+
+/// The waker of a channel's receiving half.
+struct RecvWaker {
+    inner: AtomicWaker,
+}
+
+impl RecvWaker {
+    const fn new() -> Self {
+        return Self {
+            inner: AtomicWaker::new(),
+        };
+    }
+
+    #[inline]
+    fn register(&self, cx: &Context<'_>) {
+        self.inner.register(&thread_waker(cx.waker()))
+    }
+
+    #[inline]
+    fn wake(&self) {
+        self.inner.wake()
+    }
+}
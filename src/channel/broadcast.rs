@@ -0,0 +1,257 @@
+use crate::{
+    proxying::thread_waker,
+    sync::{
+        spin::SpinLock,
+        waiters::{WaitEntry, Waiters},
+    },
+};
+use alloc::collections::VecDeque;
+use core::{
+    fmt::{Debug, Display},
+    future::poll_fn,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::Context,
+};
+use std::sync::Arc;
+
+/// Creates a bounded multi-producer, multi-consumer channel, where every receiver sees every message.
+///
+/// The channel keeps the last `capacity` messages. Receivers that fall further behind skip the
+/// oldest ones, and are notified with [`RecvError::Lagged`].
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel capacity must be positive");
+
+    let shared = Arc::new(Shared {
+        state: SpinLock::new(State {
+            buffer: VecDeque::with_capacity(capacity),
+            head: 0,
+            receivers: 1,
+        }),
+        capacity,
+        senders: AtomicUsize::new(1),
+        waiters: Waiters::new(),
+    });
+
+    return (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared, next: 0 },
+    );
+}
+
+struct Shared<T> {
+    state: SpinLock<State<T>>,
+    capacity: usize,
+    senders: AtomicUsize,
+    waiters: Waiters,
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    /// Position of the next message to be sent.
+    head: u64,
+    receivers: usize,
+}
+
+impl<T> State<T> {
+    #[inline]
+    fn tail(&self) -> u64 {
+        self.head - self.buffer.len() as u64
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a value to every active receiver, returning how many there were.
+    ///
+    /// If there are no active receivers, the value is returned back.
+    pub fn send(&self, val: T) -> Result<usize, SendError<T>> {
+        let mut state = self.shared.state.lock();
+        if state.receivers == 0 {
+            return Err(SendError(val));
+        }
+
+        if state.buffer.len() >= self.shared.capacity {
+            state.buffer.pop_front();
+        }
+        state.buffer.push_back(val);
+        state.head += 1;
+
+        let receivers = state.receivers;
+        drop(state);
+
+        self.shared.waiters.notify_all();
+        return Ok(receivers);
+    }
+
+    /// Creates a new receiver, which will see every message sent after this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock();
+        state.receivers += 1;
+        return Receiver {
+            shared: self.shared.clone(),
+            next: state.head,
+        };
+    }
+
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().receivers
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        return Self {
+            shared: self.shared.clone(),
+        };
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.waiters.notify_all();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    next: u64,
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next message.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        let shared = self.shared.clone();
+        let mut entry = WaitEntry::new(&shared.waiters);
+
+        return poll_fn(|cx| {
+            let waker = thread_waker(cx.waker());
+            let mut cx = Context::from_waker(&waker);
+            entry.poll_acquire(&mut cx, || match self.try_recv() {
+                Ok(val) => Some(Ok(val)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Lagged(n)) => Some(Err(RecvError::Lagged(n))),
+                Err(TryRecvError::Closed) => Some(Err(RecvError::Closed)),
+            })
+        })
+        .await;
+    }
+
+    /// Attempts to receive the next message without waiting for it.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let state = self.shared.state.lock();
+
+        let tail = state.tail();
+        if self.next < tail {
+            let skipped = tail - self.next;
+            self.next = tail;
+            return Err(TryRecvError::Lagged(skipped));
+        }
+
+        if self.next < state.head {
+            let val = state.buffer[(self.next - tail) as usize].clone();
+            self.next += 1;
+            return Ok(val);
+        }
+
+        if self.shared.senders.load(Ordering::Acquire) == 0 {
+            return Err(TryRecvError::Closed);
+        }
+        return Err(TryRecvError::Empty);
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Creates a new receiver, which will see every message sent after this call.
+    pub fn resubscribe(&self) -> Self {
+        let mut state = self.shared.state.lock();
+        state.receivers += 1;
+        return Self {
+            shared: self.shared.clone(),
+            next: state.head,
+        };
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    /// Creates a new receiver at the same position as this one.
+    fn clone(&self) -> Self {
+        self.shared.state.lock().receivers += 1;
+        return Self {
+            shared: self.shared.clone(),
+            next: self.next,
+        };
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared.state.lock().receivers -= 1;
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.shared.capacity)
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+/// There were no active receivers, so the value couldn't be sent.
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("There are no active receivers")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvError {
+    #[error("Every sender has been dropped")]
+    Closed,
+    #[error("The receiver lagged too far behind, skipping {0} messages")]
+    Lagged(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TryRecvError {
+    #[error("There are no new messages")]
+    Empty,
+    #[error("Every sender has been dropped")]
+    Closed,
+    #[error("The receiver lagged too far behind, skipping {0} messages")]
+    Lagged(u64),
+}
@@ -0,0 +1,271 @@
+use super::RecvWaker;
+use crate::sync::{
+    spin::SpinLock,
+    waiters::{WaitEntry, Waiters},
+};
+use alloc::collections::VecDeque;
+use core::{
+    fmt::{Debug, Display},
+    future::poll_fn,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+use futures::Stream;
+use std::{pin::Pin, sync::Arc};
+
+/// Creates a bounded multi-producer, single-consumer channel, which holds at most `capacity` messages.
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "mpsc channel capacity must be positive");
+
+    let chan = Arc::new(Chan {
+        queue: SpinLock::new(VecDeque::with_capacity(capacity)),
+        capacity,
+        senders: AtomicUsize::new(1),
+        rx_closed: AtomicBool::new(false),
+        send_waiters: Waiters::new(),
+        recv_waker: RecvWaker::new(),
+    });
+
+    return (Sender { chan: chan.clone() }, Receiver { chan });
+}
+
+struct Chan<T> {
+    queue: SpinLock<VecDeque<T>>,
+    capacity: usize,
+    senders: AtomicUsize,
+    rx_closed: AtomicBool,
+    send_waiters: Waiters,
+    recv_waker: RecvWaker,
+}
+
+impl<T> Chan<T> {
+    fn try_send(&self, val: T) -> Result<(), TrySendError<T>> {
+        if self.rx_closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Closed(val));
+        }
+
+        let mut queue = self.queue.lock();
+        if queue.len() >= self.capacity {
+            return Err(TrySendError::Full(val));
+        }
+        queue.push_back(val);
+        drop(queue);
+
+        self.recv_waker.wake();
+        return Ok(());
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(val) = self.queue.lock().pop_front() {
+            self.send_waiters.notify_one();
+            return Ok(val);
+        }
+
+        if self.senders.load(Ordering::Acquire) == 0 {
+            // A message may have been sent right before the last sender was dropped.
+            return match self.queue.lock().pop_front() {
+                Some(val) => Ok(val),
+                None => Err(TryRecvError::Disconnected),
+            };
+        }
+
+        return Err(TryRecvError::Empty);
+    }
+}
+
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a value, waiting until there's capacity for it.
+    ///
+    /// If the receiver has been closed or dropped, the value is returned back.
+    pub async fn send(&self, val: T) -> Result<(), SendError<T>> {
+        let mut val = Some(val);
+        let mut entry = WaitEntry::new(&self.chan.send_waiters);
+
+        return poll_fn(|cx| {
+            entry.poll_acquire(cx, || {
+                match self.chan.try_send(unsafe { val.take().unwrap_unchecked() }) {
+                    Ok(()) => Some(Ok(())),
+                    Err(TrySendError::Closed(v)) => Some(Err(SendError(v))),
+                    Err(TrySendError::Full(v)) => {
+                        val = Some(v);
+                        None
+                    }
+                }
+            })
+        })
+        .await;
+    }
+
+    /// Attempts to send a value without waiting for capacity.
+    #[inline]
+    pub fn try_send(&self, val: T) -> Result<(), TrySendError<T>> {
+        self.chan.try_send(val)
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.chan.rx_closed.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.chan.capacity
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.chan.senders.fetch_add(1, Ordering::Relaxed);
+        return Self {
+            chan: self.chan.clone(),
+        };
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.chan.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.chan.recv_waker.wake();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives the next value, returning `None` once every sender has been dropped and the channel is empty.
+    #[inline]
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.chan.try_recv() {
+            Ok(val) => return Poll::Ready(Some(val)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        self.chan.recv_waker.register(cx);
+        return match self.chan.try_recv() {
+            Ok(val) => Poll::Ready(Some(val)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        };
+    }
+
+    #[inline]
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.chan.try_recv()
+    }
+
+    /// Closes the receiving half of the channel, without dropping it.
+    ///
+    /// Messages already in the channel can still be received.
+    pub fn close(&mut self) {
+        self.chan.rx_closed.store(true, Ordering::Release);
+        self.chan.send_waiters.notify_all();
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+        self.chan.queue.lock().clear();
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.chan.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.chan.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The receiver was closed, so the value couldn't be sent.
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("The channel is closed")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(val) | TrySendError::Closed(val) => val,
+        }
+    }
+}
+
+impl<T> Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Closed(_) => f.write_str("Closed(..)"),
+        }
+    }
+}
+
+impl<T> Display for TrySendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("The channel is full"),
+            TrySendError::Closed(_) => f.write_str("The channel is closed"),
+        }
+    }
+}
+
+impl<T> std::error::Error for TrySendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TryRecvError {
+    #[error("The channel is empty")]
+    Empty,
+    #[error("Every sender has been dropped")]
+    Disconnected,
+}
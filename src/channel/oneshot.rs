@@ -0,0 +1,166 @@
+use super::RecvWaker;
+use core::{
+    cell::UnsafeCell,
+    fmt::Debug,
+    future::Future,
+    mem::ManuallyDrop,
+    pin::Pin,
+    sync::atomic::{AtomicU8, Ordering},
+    task::{Context, Poll},
+};
+use std::sync::Arc;
+
+const VALUE_SENT: u8 = 1;
+const TX_CLOSED: u8 = 1 << 1;
+const RX_CLOSED: u8 = 1 << 2;
+
+/// Creates a channel that sends a single value between two tasks.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: AtomicU8::new(0),
+        value: UnsafeCell::new(None),
+        waker: RecvWaker::new(),
+    });
+
+    return (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    );
+}
+
+struct Inner<T> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+    waker: RecvWaker,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends the value to the receiver, returning it back if the receiver has been dropped.
+    pub fn send(self, val: T) -> Result<(), T> {
+        let this = ManuallyDrop::new(self);
+        let inner = unsafe { core::ptr::read(&this.inner) };
+
+        // The receiver won't touch the value until `VALUE_SENT` is set.
+        unsafe { *inner.value.get() = Some(val) };
+
+        let mut state = inner.state.load(Ordering::Acquire);
+        loop {
+            if state & RX_CLOSED != 0 {
+                let val = unsafe { (*inner.value.get()).take().unwrap_unchecked() };
+                inner.state.fetch_or(TX_CLOSED, Ordering::AcqRel);
+                inner.waker.wake();
+                return Err(val);
+            }
+
+            match inner.state.compare_exchange_weak(
+                state,
+                state | VALUE_SENT | TX_CLOSED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(e) => state = e,
+            }
+        }
+
+        inner.waker.wake();
+        return Ok(());
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.inner.state.load(Ordering::Acquire) & RX_CLOSED != 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(TX_CLOSED, Ordering::AcqRel);
+        self.inner.waker.wake();
+    }
+}
+
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to receive the value without waiting for it.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let state = self.inner.state.load(Ordering::Acquire);
+        if state & VALUE_SENT != 0 {
+            return match unsafe { (*self.inner.value.get()).take() } {
+                Some(val) => Ok(val),
+                None => Err(TryRecvError::Closed),
+            };
+        } else if state & TX_CLOSED != 0 {
+            return Err(TryRecvError::Closed);
+        }
+        return Err(TryRecvError::Empty);
+    }
+
+    /// Prevents the sender from sending a value, without dropping the receiver.
+    #[inline]
+    pub fn close(&mut self) {
+        self.inner.state.fetch_or(RX_CLOSED, Ordering::AcqRel);
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.try_recv() {
+            Ok(val) => return Poll::Ready(Ok(val)),
+            Err(TryRecvError::Closed) => return Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        self.inner.waker.register(cx);
+        return match self.try_recv() {
+            Ok(val) => Poll::Ready(Ok(val)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        };
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(RX_CLOSED, Ordering::AcqRel);
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("The sender was dropped without sending a value")]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TryRecvError {
+    #[error("No value has been sent yet")]
+    Empty,
+    #[error("The sender was dropped without sending a value")]
+    Closed,
+}
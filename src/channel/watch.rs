@@ -0,0 +1,242 @@
+use crate::{
+    proxying::thread_waker,
+    sync::{
+        spin::SpinLock,
+        waiters::{WaitEntry, Waiters},
+    },
+};
+use core::{
+    fmt::{Debug, Display},
+    future::poll_fn,
+    ops::Deref,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::Context,
+};
+use std::sync::Arc;
+
+/// Creates a single-producer, multi-consumer channel that only retains the last value sent.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: SpinLock::new(Arc::new(init)),
+        version: AtomicUsize::new(0),
+        receivers: AtomicUsize::new(1),
+        closed: AtomicBool::new(false),
+        waiters: Waiters::new(),
+    });
+
+    return (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared, seen: 0 },
+    );
+}
+
+struct Shared<T> {
+    /// Only locked to swap or clone the pointer, so that borrowing the value never holds the lock
+    value: SpinLock<Arc<T>>,
+    version: AtomicUsize,
+    receivers: AtomicUsize,
+    closed: AtomicBool,
+    waiters: Waiters,
+}
+
+/// A reference to the value inside a watch channel, at the time it was borrowed.
+///
+/// It doesn't lock the channel, so new values can be sent while it's alive.
+pub struct Ref<T> {
+    inner: Arc<T>,
+}
+
+impl<T> Deref for Ref<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a new value, notifying every receiver.
+    ///
+    /// If there are no active receivers, the value is returned back.
+    pub fn send(&self, val: T) -> Result<(), SendError<T>> {
+        if self.shared.receivers.load(Ordering::Acquire) == 0 {
+            return Err(SendError(val));
+        }
+
+        self.send_replace(val);
+        return Ok(());
+    }
+
+    /// Replaces the current value, notifying every receiver even if there are none.
+    ///
+    /// The previous value is returned behind an [`Arc`], since it may still be borrowed.
+    pub fn send_replace(&self, val: T) -> Arc<T> {
+        let prev = core::mem::replace(&mut *self.shared.value.lock(), Arc::new(val));
+        self.notify();
+        return prev;
+    }
+
+    /// Modifies the current value in place, notifying every receiver.
+    ///
+    /// If the value is currently borrowed, it's cloned first, so that borrows keep seeing the previous value.
+    pub fn send_modify<F: FnOnce(&mut T)>(&self, f: F)
+    where
+        T: Clone,
+    {
+        f(Arc::make_mut(&mut self.shared.value.lock()));
+        self.notify();
+    }
+
+    #[inline]
+    pub fn borrow(&self) -> Ref<T> {
+        Ref {
+            inner: self.shared.value.lock().clone(),
+        }
+    }
+
+    /// Creates a new receiver, which sees the current value as already seen.
+    pub fn subscribe(&self) -> Receiver<T> {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        return Receiver {
+            shared: self.shared.clone(),
+            seen: self.shared.version.load(Ordering::Acquire),
+        };
+    }
+
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.receivers.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    fn notify(&self) {
+        self.shared.version.fetch_add(1, Ordering::AcqRel);
+        self.shared.waiters.notify_all();
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.waiters.notify_all();
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    seen: usize,
+}
+
+impl<T> Receiver<T> {
+    /// Waits until a value that this receiver hasn't seen yet is sent, and marks it as seen.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        let shared = self.shared.clone();
+        let mut entry = WaitEntry::new(&shared.waiters);
+
+        return poll_fn(|cx| {
+            let waker = thread_waker(cx.waker());
+            let mut cx = Context::from_waker(&waker);
+            entry.poll_acquire(&mut cx, || {
+                let version = shared.version.load(Ordering::Acquire);
+                if version != self.seen {
+                    self.seen = version;
+                    return Some(Ok(()));
+                } else if shared.closed.load(Ordering::Acquire) {
+                    return Some(Err(RecvError));
+                }
+                return None;
+            })
+        })
+        .await;
+    }
+
+    /// Returns `true` if a value that this receiver hasn't seen yet has been sent.
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        if self.shared.version.load(Ordering::Acquire) != self.seen {
+            return Ok(true);
+        } else if self.shared.closed.load(Ordering::Acquire) {
+            return Err(RecvError);
+        }
+        return Ok(false);
+    }
+
+    /// Returns a reference to the latest value, without marking it as seen.
+    #[inline]
+    pub fn borrow(&self) -> Ref<T> {
+        Ref {
+            inner: self.shared.value.lock().clone(),
+        }
+    }
+
+    /// Returns a reference to the latest value, marking it as seen.
+    pub fn borrow_and_update(&mut self) -> Ref<T> {
+        let value = self.shared.value.lock();
+        self.seen = self.shared.version.load(Ordering::Acquire);
+        return Ref {
+            inner: value.clone(),
+        };
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        return Self {
+            shared: self.shared.clone(),
+            seen: self.seen,
+        };
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared.receivers.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T: Debug> Debug for Sender<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Debug> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+/// There were no active receivers, so the value couldn't be sent.
+pub struct SendError<T>(pub T);
+
+impl<T> Debug for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("There are no active receivers")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("The sender has been dropped")]
+pub struct RecvError;
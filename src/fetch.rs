@@ -1,7 +1,7 @@
 use self::sys::fetch_header_t;
 use crate::{
     fetch::sys::{fetch_attrs_t, fetch_status_t, GetResponseBytes, GetResponseChunks, SendRequest},
-    future::block_on,
+    future::{block_on, fallible_event, Event, Promise},
     value::JsValue,
 };
 use alloc::{alloc::Layout, borrow::Cow};
@@ -14,7 +14,6 @@ use http::{Response, StatusCode};
 use libc::c_void;
 use libstd::io::{BufRead, Cursor, ErrorKind, Read};
 use pin_project::pin_project;
-use utils_atomics::channel::once::{async_channel, AsyncReceiver, AsyncSender};
 
 pub async fn get(url: &CStr) -> Result<Response<ResponseBody>, RequestError> {
     Builder::new().send(Method::GET, url).await
@@ -68,16 +67,18 @@ impl<'a> Builder<'a> {
             handle: *mut c_void,
             user_data: *mut c_void,
         ) {
-            let send = Box::from_raw(
-                user_data.cast::<AsyncSender<Result<Response<ResponseBody>, RequestError>>>(),
-            );
+            let event =
+                Box::from_raw(user_data.cast::<Event<Response<ResponseBody>, RequestError>>());
 
-            send.send(handle_response(
+            match handle_response(
                 status,
                 status_code,
                 std::slice::from_raw_parts(headers, headers_len),
                 handle,
-            ));
+            ) {
+                Ok(response) => event.fulfill(response),
+                Err(e) => event.reject(e),
+            }
         }
 
         unsafe {
@@ -107,16 +108,16 @@ impl<'a> Builder<'a> {
                 body_len,
             };
 
-            let (send, recv) = async_channel::<Result<Response<ResponseBody>, RequestError>>();
+            let (event, promise) = fallible_event::<Response<ResponseBody>, RequestError>();
             SendRequest(
                 method.as_ptr(),
                 url.as_ptr(),
                 attrs,
                 Some(on_response),
-                Box::into_raw(Box::new(send)).cast(),
+                Box::into_raw(Box::new(event)).cast(),
             );
 
-            return Ok(recv.await.transpose()?.unwrap());
+            return promise.await;
         }
     }
 }
@@ -150,27 +151,26 @@ impl ResponseBody {
             len: usize,
             user_data: *mut c_void,
         ) {
-            let send =
-                Box::from_raw(user_data.cast::<AsyncSender<Result<Vec<u8>, RequestError>>>());
+            let event = Box::from_raw(user_data.cast::<Event<Vec<u8>, RequestError>>());
 
-            send.send(match status {
-                fetch_status_t::Sent => Ok(Vec::from_raw_parts(ptr, len, len)),
-                fetch_status_t::TimedOut => Err(RequestError::TimedOut),
-                fetch_status_t::Exception => Err(RequestError::Unexpected),
+            match status {
+                fetch_status_t::Sent => event.fulfill(Vec::from_raw_parts(ptr, len, len)),
+                fetch_status_t::TimedOut => event.reject(RequestError::TimedOut),
+                fetch_status_t::Exception => event.reject(RequestError::Unexpected),
                 fetch_status_t::Ended => unreachable_unchecked(),
-            });
+            }
         }
 
         unsafe {
-            let (send, recv) = async_channel::<Result<Vec<u8>, RequestError>>();
+            let (event, promise) = fallible_event::<Vec<u8>, RequestError>();
             GetResponseBytes(
                 self.inner.as_handle().cast(),
                 Some(on_bytes_pre),
                 std::ptr::null_mut(),
                 Some(on_bytes_post),
-                Box::into_raw(Box::new(send)).cast(),
+                Box::into_raw(Box::new(event)).cast(),
             );
-            return recv.await.unwrap();
+            return promise.await;
         }
     }
 
@@ -188,35 +188,33 @@ impl ResponseBody {
             len: usize,
             user_data: *mut c_void,
         ) {
-            let user_data = user_data.cast::<AsyncSender<ResponseChunk>>();
+            let user_data = user_data.cast::<Event<ResponseChunk, RequestError>>();
             match status {
                 fetch_status_t::Sent => {
                     let chunk = Vec::from_raw_parts(ptr, len, len);
-                    let (new_send, new_recv) = async_channel();
-                    std::mem::replace(&mut *user_data, new_send)
-                        .send(ResponseChunk::Ok(chunk, new_recv));
-                }
-                fetch_status_t::TimedOut => {
-                    let send = Box::from_raw(user_data);
-                    send.send(ResponseChunk::Err(RequestError::TimedOut));
+                    let (next, promise) = fallible_event();
+                    std::mem::replace(&mut *user_data, next)
+                        .fulfill(ResponseChunk::Ok(chunk, promise));
                 }
+                fetch_status_t::TimedOut => Box::from_raw(user_data).reject(RequestError::TimedOut),
                 fetch_status_t::Exception => {
-                    let send = Box::from_raw(user_data);
-                    send.send(ResponseChunk::Err(RequestError::Unexpected));
+                    Box::from_raw(user_data).reject(RequestError::Unexpected)
                 }
-                fetch_status_t::Ended => drop(Box::from_raw(user_data)),
+                fetch_status_t::Ended => Box::from_raw(user_data).fulfill(ResponseChunk::End),
             }
         }
         unsafe {
-            let (send, recv) = async_channel::<ResponseChunk>();
+            let (event, promise) = fallible_event::<ResponseChunk, RequestError>();
             GetResponseChunks(
                 self.inner.as_handle().cast(),
                 Some(on_bytes_pre),
                 std::ptr::null_mut(),
                 Some(on_bytes_post),
-                Box::into_raw(Box::new(send)).cast(),
+                Box::into_raw(Box::new(event)).cast(),
             );
-            return ResponseChunks { recv: Some(recv) };
+            return ResponseChunks {
+                recv: Some(promise),
+            };
         }
     }
 
@@ -229,7 +227,7 @@ impl ResponseBody {
 #[pin_project]
 pub struct ResponseChunks {
     #[pin]
-    recv: Option<AsyncReceiver<ResponseChunk>>,
+    recv: Option<Promise<ResponseChunk, RequestError>>,
 }
 
 impl Stream for ResponseChunks {
@@ -245,15 +243,15 @@ impl Stream for ResponseChunks {
         };
 
         match ready!(recv.poll(cx)) {
-            Some(ResponseChunk::Ok(chunk, recv)) => {
+            Ok(ResponseChunk::Ok(chunk, recv)) => {
                 this.recv.set(Some(recv));
                 return Poll::Ready(Some(Ok(chunk)));
             }
-            Some(ResponseChunk::Err(e)) => {
+            Err(e) => {
                 this.recv.set(None);
                 return Poll::Ready(Some(Err(e)));
             }
-            None => {
+            Ok(ResponseChunk::End) => {
                 this.recv.set(None);
                 return Poll::Ready(None);
             }
@@ -311,7 +309,7 @@ impl ResponseReader {
     }
 }
 
-#[docfg(all(feature = "asyncify", feature = "proxying"))]
+#[docfg(feature = "asyncify")]
 impl std::io::Read for ResponseReader {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> libstd::io::Result<usize> {
@@ -319,7 +317,7 @@ impl std::io::Read for ResponseReader {
     }
 }
 
-#[docfg(all(feature = "asyncify", feature = "proxying"))]
+#[docfg(feature = "asyncify")]
 impl std::io::BufRead for ResponseReader {
     #[inline]
     fn fill_buf(&mut self) -> libstd::io::Result<&[u8]> {
@@ -369,8 +367,8 @@ impl From<RequestError> for std::io::Error {
 }
 
 enum ResponseChunk {
-    Ok(Vec<u8>, AsyncReceiver<Self>),
-    Err(RequestError),
+    Ok(Vec<u8>, Promise<Self, RequestError>),
+    End,
 }
 
 unsafe fn handle_response(
@@ -1,19 +1,13 @@
 #[cfg(feature = "proxying")]
 use crate::proxying::Queue;
-use crate::{
-    set_timeout,
-    sys::{self, em_promise_t, PthreadWrapper},
-};
+use crate::sys::{self, em_promise_t, PthreadWrapper};
 use docfg::docfg;
 #[cfg(feature = "proxying")]
-use futures::{
-    executor::LocalPool,
-    task::{LocalFutureObj, LocalSpawn, LocalSpawnExt},
-};
-use pin_project::pin_project;
+use std::os::unix::thread::JoinHandleExt;
 use std::{
     any::Any,
-    cell::{Cell, RefCell},
+    cell::Cell,
+    convert::Infallible,
     ffi::c_void,
     future::Future,
     marker::PhantomData,
@@ -21,32 +15,54 @@ use std::{
     ops::Deref,
     pin::Pin,
     rc::Rc,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{ready, Context, Poll, Waker},
-    time::Duration,
-};
-use utils_atomics::{
-    channel::once::{async_channel, AsyncReceiver},
-    flag::mpsc::async_flag,
 };
+use utils_atomics::channel::once::{async_channel, AsyncReceiver, AsyncSender};
 
-pub async fn sleep(dur: Duration) {
-    let (send, recv) = async_flag();
-    set_timeout(dur, move || send.mark());
-    return recv.await;
-}
+pub use crate::time::sleep;
+#[cfg(feature = "proxying")]
+pub use idle::spawn_idle;
+pub use idle::{idle, time_remaining, Idle, IdleDeadline};
+#[cfg(feature = "proxying")]
+pub use js::future_to_promise;
+pub use js::JsFuture;
 
-#[docfg(all(feature = "asyncify", feature = "proxying"))]
+mod idle;
+mod js;
+#[cfg(feature = "proxying")]
+pub use local::{LocalRuntimeHandle, RemoteRuntimeHandle, RuntimeMetrics, TaskMetrics};
+#[cfg(feature = "proxying")]
+mod local;
+
+/// Blocks the current thread until `fut` completes, yielding to the event loop while it's pending.
+///
+/// Wakes from the current thread resolve the awaited promise directly, while wakes from other threads are
+/// proxied back to it.
+///
+/// # Panics
+/// Without the `proxying` feature, there's no way to reach the blocked thread, so waking `fut` from
+/// another thread panics instead of leaving the caller blocked forever.
+#[docfg(feature = "asyncify")]
 pub fn block_on<Fut>(fut: Fut) -> Fut::Output
 where
     Fut: Future,
 {
     thread_local! {
-        static CURRENT_EVENT: Cell<Option<Event<Inner>>> = Cell::new(None);
+        static CURRENT_EVENT: Cell<Option<Event<()>>> = Cell::new(None);
+    }
+
+    fn notify() {
+        if let Some(current_event) = CURRENT_EVENT.take() {
+            current_event.fulfill(());
+        }
     }
 
     struct Wake {
-        queue: Queue<'static>,
+        woken: AtomicBool,
         thread: PthreadWrapper,
     }
 
@@ -57,33 +73,44 @@ where
         }
 
         fn wake_by_ref(self: &std::sync::Arc<Self>) {
-            assert!(self.queue.proxy(&self.thread, move || {
-                if let Some(current_event) = CURRENT_EVENT.take() {
-                    let (new_event, promise) = event::<Inner>();
-                    CURRENT_EVENT.set(Some(new_event));
-                    current_event.fulfill(promise.into_raw());
-                }
-            }));
+            if self.woken.swap(true, Ordering::AcqRel) {
+                return;
+            }
+
+            if self.thread.is_current() {
+                return notify();
+            }
+
+            #[cfg(feature = "proxying")]
+            assert!(Queue::system().proxy(&self.thread, notify));
+            #[cfg(not(feature = "proxying"))]
+            panic!("block_on can only be woken from its own thread without the `proxying` feature");
         }
     }
 
-    let queue = Arc::new(Wake {
-        queue: Queue::new(),
+    let wake = Arc::new(Wake {
+        woken: AtomicBool::new(false),
         thread: PthreadWrapper::current(),
     });
 
-    let waker = Waker::from(queue.clone());
+    let waker = Waker::from(wake.clone());
     let mut cx = Context::from_waker(&waker);
 
-    let (event, mut promise) = event::<Inner>();
-    CURRENT_EVENT.set(Some(event));
-
     let mut fut = std::pin::pin!(fut);
     loop {
-        match fut.as_mut().poll(&mut cx) {
-            std::task::Poll::Ready(val) => return val,
-            std::task::Poll::Pending => unsafe { promise = Promise::from_raw(promise.block_on()) },
+        if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+            return val;
+        }
+
+        let (event, promise) = event::<()>();
+        CURRENT_EVENT.set(Some(event));
+
+        // Wakes that happened while polling won't find the event, so we check for them here
+        if !wake.woken.swap(false, Ordering::AcqRel) {
+            promise.block_on().unwrap_or_else(|e| match e {});
+            wake.woken.store(false, Ordering::Release);
         }
+        CURRENT_EVENT.set(None);
     }
 }
 
@@ -93,152 +120,266 @@ where
     Fut: 'static + Future,
     Fut::Output: 'static,
 {
-    #[pin_project]
-    struct Task<Fut> {
-        #[pin]
-        fut: Fut,
-        queue: Weak<Queue<'static>>,
-        _phtm: PhantomData<*mut ()>,
-    }
-
-    impl<Fut: Future<Output = ()>> Future for Task<Fut> {
-        type Output = ();
-
-        fn poll(
-            self: std::pin::Pin<&mut Self>,
-            cx: &mut Context<'_>,
-        ) -> std::task::Poll<Self::Output> {
-            let this = self.project();
-
-            struct Wake {
-                parent: Waker,
-                queue: Weak<Queue<'static>>,
-                thread: PthreadWrapper,
-            }
+    spawn_local_named(fut, None)
+}
 
-            impl std::task::Wake for Wake {
-                #[inline]
-                fn wake(self: std::sync::Arc<Self>) {
-                    self.wake_by_ref()
-                }
+#[cfg(feature = "proxying")]
+pub(crate) fn spawn_local_named<Fut>(fut: Fut, name: Option<Arc<str>>) -> JoinHandle<Fut::Output>
+where
+    Fut: 'static + Future,
+    Fut::Output: 'static,
+{
+    let (send, handle) = join_channel(name.clone());
+    local::LOCAL_RUNTIME.with(|rt| rt.spawn(send.wrap(fut), name));
+    return handle;
+}
 
-                fn wake_by_ref(self: &std::sync::Arc<Self>) {
-                    self.parent.wake_by_ref();
-                    if let Some(queue) = self.queue.upgrade() {
-                        queue.proxy(&self.thread, move || LOCAL_RUNTIME.with(|rt| rt.enqueue()));
-                    }
-                }
-            }
+/// Returns a snapshot of the state of the local runtime of the current thread.
+#[docfg(feature = "proxying")]
+#[inline]
+pub fn local_metrics() -> RuntimeMetrics {
+    local::LOCAL_RUNTIME.with(|rt| rt.metrics())
+}
 
-            let waker = Waker::from(Arc::new(Wake {
-                parent: cx.waker().clone(),
-                queue: this.queue.clone(),
-                thread: PthreadWrapper::current(),
-            }));
-            let mut cx = Context::from_waker(&waker);
-            return this.fut.poll(&mut cx);
-        }
-    }
+/// Returns a snapshot of the tasks in the local runtime of the current thread, in the order they were spawned.
+///
+/// If called from inside a task, that task isn't included.
+#[docfg(feature = "proxying")]
+#[inline]
+pub fn local_tasks() -> Vec<TaskMetrics> {
+    local::LOCAL_RUNTIME.with(|rt| rt.tasks())
+}
 
-    struct Pool {
-        pool: RefCell<LocalPool>,
-        backlog: RefCell<Vec<LocalFutureObj<'static, ()>>>,
+/// Logs the state of the local runtime of the current thread, and of each of its tasks, to the browser's console.
+#[docfg(all(feature = "proxying", feature = "console"))]
+pub fn dump_tasks() {
+    use std::fmt::Write;
+
+    let metrics = local_metrics();
+    let mut out = format!(
+        "Local runtime: {} live tasks ({} ready), {} spawned, {} polls in {:?}",
+        metrics.live_tasks,
+        metrics.ready_tasks,
+        metrics.spawned_tasks,
+        metrics.total_polls,
+        metrics.total_poll_time
+    );
+
+    for task in local_tasks() {
+        let _ = write!(
+            out,
+            "\n  #{} {}: {}, {} polls in {:?}, spawned {:?} ago",
+            task.id,
+            task.name.as_deref().unwrap_or("<unnamed>"),
+            if task.is_ready { "ready" } else { "waiting" },
+            task.polls,
+            task.poll_time,
+            task.spawned_at.elapsed()
+        );
     }
 
-    impl Pool {
-        fn push(&self, queue: &Arc<Queue<'static>>, fut: impl 'static + Future<Output = ()>) {
-            let fut = Task {
-                fut,
-                queue: Arc::downgrade(queue),
-                _phtm: PhantomData,
-            };
+    // Formatted values can't contain nul bytes, but task names can
+    let out = std::ffi::CString::new(out.replace('\0', "")).unwrap();
+    crate::console::console_log(&out);
+}
 
-            if let Some(pool) = self.pool.try_borrow_mut().ok() {
-                pool.spawner()
-                    .spawn_local(fut)
-                    .expect("Error spawning local task");
-            } else {
-                self.backlog
-                    .borrow_mut()
-                    .push(LocalFutureObj::new(Box::new(fut)));
-            }
-        }
-    }
+/// Sets how many tasks the local runtime of the current thread polls before yielding to the event loop.
+///
+/// Defaults to 128. A budget of zero is treated as one.
+#[docfg(feature = "proxying")]
+#[inline]
+pub fn set_poll_budget(budget: usize) {
+    local::LOCAL_RUNTIME.with(|rt| rt.set_budget(budget))
+}
 
-    struct Runtime {
-        queue: Arc<Queue<'static>>,
-        tasks: Pool,
-        is_running: Cell<bool>,
-    }
+/// Yields to the event loop, resuming the current task after a macrotask boundary.
+///
+/// Unlike a plain wake-up, this lets the browser render and handle input before the task continues.
+#[doc(alias = "emscripten_set_immediate")]
+#[inline]
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
 
-    impl Runtime {
-        pub fn new() -> Self {
-            return Runtime {
-                queue: Arc::new(Queue::new()),
-                tasks: Pool {
-                    pool: RefCell::new(LocalPool::new()),
-                    backlog: RefCell::new(Vec::new()),
-                },
-                is_running: Cell::new(false),
-            };
-        }
+/// Future returned by [`yield_now`].
+#[derive(Debug)]
+pub struct YieldNow {
+    yielded: bool,
+}
 
-        pub fn spawn<Fut: 'static + Future>(&self, fut: Fut) -> JoinHandle<Fut::Output> {
-            use futures::FutureExt;
-            use std::panic::AssertUnwindSafe;
+impl Future for YieldNow {
+    type Output = ();
 
-            let (send, recv) = async_channel();
-            self.tasks.push(&self.queue, async move {
-                send.send(AssertUnwindSafe(fut).catch_unwind().await)
-            });
-            self.enqueue();
-            return JoinHandle { recv };
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        unsafe extern "C" fn wake(waker: *mut c_void) {
+            Box::from_raw(waker.cast::<Waker>()).wake();
         }
 
-        fn enqueue(&self) {
-            if !self.is_running.replace(true) {
-                assert!(self
-                    .queue
-                    .proxy_local(|| LOCAL_RUNTIME.with(|rt| rt.poll())));
-            }
+        if self.yielded {
+            return Poll::Ready(());
         }
 
-        fn poll(&self) {
-            let mut pool = RefCell::borrow_mut(&self.tasks.pool);
-            pool.run_until_stalled();
-            self.is_running.set(false);
+        self.yielded = true;
+        let waker = Box::new(cx.waker().clone());
+        unsafe { sys::emscripten_set_immediate(Some(wake), Box::into_raw(waker).cast()) };
+        return Poll::Pending;
+    }
+}
 
-            let spawner = pool.spawner();
-            for future in self.tasks.backlog.borrow_mut().drain(..) {
-                spawner
-                    .spawn_local_obj(future)
-                    .expect("Error spawning local task");
+/// Wraps `fut` so that its output (or panic payload) is sent to the returned [`JoinHandle`], which can also abort it.
+#[inline]
+pub(crate) fn joinable<Fut: Future>(
+    fut: Fut,
+) -> (impl Future<Output = ()>, JoinHandle<Fut::Output>) {
+    let (send, handle) = join_channel(None);
+    return (send.wrap(fut), handle);
+}
+
+pub(crate) fn join_channel<T>(name: Option<Arc<str>>) -> (JoinSender<T>, JoinHandle<T>) {
+    let (send, recv) = async_channel();
+    let (abort, registration) = futures::future::AbortHandle::new_pair();
+    return (
+        JoinSender {
+            send,
+            registration,
+            name: name.clone(),
+        },
+        JoinHandle {
+            recv,
+            abort: AbortHandle { inner: abort },
+            name,
+        },
+    );
+}
+
+/// The sending half of a [`JoinHandle`], which may be created before the task it joins.
+pub(crate) struct JoinSender<T> {
+    send: AsyncSender<Result<T, Box<dyn Any + Send + 'static>>>,
+    registration: futures::future::AbortRegistration,
+    name: Option<Arc<str>>,
+}
+
+impl<T> JoinSender<T> {
+    pub fn wrap<Fut: Future<Output = T>>(self, fut: Fut) -> impl Future<Output = ()> {
+        use futures::FutureExt;
+        use std::panic::AssertUnwindSafe;
+
+        let fut = futures::future::Abortable::new(
+            AssertUnwindSafe(fut).catch_unwind(),
+            self.registration,
+        );
+        let send = self.send;
+        let name = self.name;
+
+        return crate::task::scope_name(name.clone(), async move {
+            let res = fut.await;
+            if let Some(name) = name {
+                match res {
+                    Ok(Ok(_)) => log::debug!("Task '{name}' completed"),
+                    Ok(Err(_)) => log::debug!("Task '{name}' panicked"),
+                    Err(_) => log::debug!("Task '{name}' was aborted"),
+                }
             }
-        }
-    }
 
-    thread_local! {
-        static LOCAL_RUNTIME: Runtime = Runtime::new();
+            // If aborted, dropping the sender resolves the handle with `JoinError::Aborted`
+            if let Ok(res) = res {
+                send.send(res)
+            }
+        });
     }
+}
 
-    return LOCAL_RUNTIME.with(|rt| rt.spawn(fut));
+/// Spawns the future returned by `f` onto the local runtime of `thread`, as if it was spawned there by [`spawn_local`].
+///
+/// Unlike with [`spawn_local`], the future doesn't need to be [`Send`], only its factory and output do. If `thread`
+/// can't run the factory, the returned handle resolves to [`JoinError::Aborted`].
+#[docfg(feature = "proxying")]
+pub fn spawn_on<F, Fut>(thread: &impl JoinHandleExt, f: F) -> JoinHandle<Fut::Output>
+where
+    F: 'static + Send + FnOnce() -> Fut,
+    Fut: 'static + Future,
+    Fut::Output: 'static + Send,
+{
+    let (send, handle) = join_channel(None);
+    Queue::system().proxy(thread, move || {
+        // Calling `f` inside the task lets its panics reach the handle too
+        drop(spawn_local(send.wrap(async move { f().await })));
+    });
+    return handle;
 }
 
 pub struct JoinHandle<T> {
     pub(crate) recv: AsyncReceiver<Result<T, Box<dyn Any + Send + 'static>>>,
+    pub(crate) abort: AbortHandle,
+    pub(crate) name: Option<Arc<str>>,
+}
+
+/// A handle that can abort a spawned task without awaiting it.
+///
+/// It can be cloned and sent to other threads, but the task is always dropped on the thread it was spawned on.
+#[derive(Debug, Clone)]
+pub struct AbortHandle {
+    inner: futures::future::AbortHandle,
+}
+
+impl AbortHandle {
+    /// Aborts the task, which will be dropped the next time its runtime runs.
+    ///
+    /// If the task had already completed, this does nothing.
+    #[inline]
+    pub fn abort(&self) {
+        self.inner.abort()
+    }
+
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.inner.is_aborted()
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum JoinError {
     #[error("The task was aborted")]
     Aborted,
-    #[error("The task panicked")]
-    Panic(Box<dyn Any + Send + 'static>),
+    #[error(
+        "The task '{}' panicked: {}",
+        .name.as_deref().unwrap_or("<unnamed>"),
+        panic_message(.payload.as_ref())
+    )]
+    Panic {
+        /// The name given to the task with [`task::Builder`](crate::task::Builder), if any
+        name: Option<Arc<str>>,
+        payload: Box<dyn Any + Send + 'static>,
+    },
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send + 'static)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        return msg;
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        return msg;
+    }
+    return "Box<dyn Any>";
 }
 
 impl<T> JoinHandle<T> {
-    pub fn abort(self) {
-        todo!()
+    /// Aborts the task, which will be dropped the next time its runtime runs.
+    ///
+    /// Awaiting the handle afterwards returns [`JoinError::Aborted`], unless the task had already completed.
+    #[inline]
+    pub fn abort(&self) {
+        self.abort.abort()
+    }
+
+    #[inline]
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Returns the name given to the task with [`task::Builder`](crate::task::Builder), if any.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 }
 
@@ -251,13 +392,23 @@ impl<T> Future for JoinHandle<T> {
     ) -> std::task::Poll<Self::Output> {
         return Poll::Ready(match ready!(Pin::new(&mut self.recv).poll(cx)) {
             Some(Ok(val)) => Ok(val),
-            Some(Err(e)) => Err(JoinError::Panic(e)),
+            Some(Err(payload)) => Err(JoinError::Panic {
+                name: self.name.clone(),
+                payload,
+            }),
             None => Err(JoinError::Aborted),
         });
     }
 }
 
+/// Creates an [`Event`] and the [`Promise`] it fulfills.
+#[inline]
 pub fn event<T>() -> (Event<T>, Promise<T>) {
+    fallible_event()
+}
+
+/// Creates an [`Event`] and the [`Promise`] it either fulfills or rejects.
+pub fn fallible_event<T, E>() -> (Event<T, E>, Promise<T, E>) {
     let raw = Rc::new(RawPromise::new());
 
     return (
@@ -267,17 +418,18 @@ pub fn event<T>() -> (Event<T>, Promise<T>) {
         },
         Promise {
             raw: ManuallyDrop::new(Inner::Shared(raw)),
+            polled: None,
             _phtm: PhantomData,
         },
     );
 }
 
-pub struct Event<T> {
+pub struct Event<T, E = Infallible> {
     raw: Rc<RawPromise>,
-    _phtm: PhantomData<T>,
+    _phtm: PhantomData<(T, E)>,
 }
 
-impl<T> Event<T> {
+impl<T, E> Event<T, E> {
     #[inline]
     pub fn fulfill(self, val: T) {
         unsafe { self.fulfill_ref(val) };
@@ -287,15 +439,38 @@ impl<T> Event<T> {
     pub unsafe fn fulfill_ref(&self, val: T) {
         unsafe { self.raw.fulfill(val) };
     }
+
+    #[inline]
+    pub fn reject(self, err: E) {
+        unsafe { self.reject_ref(err) };
+    }
+
+    #[inline]
+    pub unsafe fn reject_ref(&self, err: E) {
+        unsafe { self.raw.reject(err) };
+    }
 }
 
-pub struct Promise<T> {
+/// A value that will be available once an [`Event`] settles it.
+///
+/// It can be awaited like any other future, or blocked on with asyncify.
+pub struct Promise<T, E = Infallible> {
     raw: ManuallyDrop<Inner>,
-    _phtm: PhantomData<T>,
+    /// Set once the promise has been polled, after which its value is delivered here
+    polled: Option<Rc<Polled<T, E>>>,
+    _phtm: PhantomData<(T, E)>,
+}
+
+struct Polled<T, E> {
+    result: Cell<Option<Result<Box<T>, Box<E>>>>,
+    waker: Cell<Option<Waker>>,
 }
 
-impl<T> Promise<T> {
+impl<T, E> Promise<T, E> {
+    /// # Panics
+    /// Panics if the promise has already been polled.
     pub fn into_raw(self) -> Inner {
+        assert!(self.polled.is_none(), "promise has already been polled");
         let mut this = ManuallyDrop::new(self);
         return unsafe { ManuallyDrop::take(&mut this.raw) };
     }
@@ -303,30 +478,306 @@ impl<T> Promise<T> {
     pub unsafe fn from_raw(raw: Inner) -> Self {
         return Self {
             raw: ManuallyDrop::new(raw),
+            polled: None,
             _phtm: PhantomData,
         };
     }
 
     #[docfg(feature = "asyncify")]
     #[inline]
-    pub fn block_on(self) -> T {
-        return *self.block_on_boxed();
+    pub fn block_on(self) -> Result<T, E> {
+        return match self.block_on_boxed() {
+            Ok(val) => Ok(*val),
+            Err(e) => Err(*e),
+        };
     }
 
     #[docfg(feature = "asyncify")]
-    pub fn block_on_boxed(self) -> Box<T> {
+    pub fn block_on_boxed(self) -> Result<Box<T>, Box<E>> {
         let mut this = ManuallyDrop::new(self);
         let result = this.raw.block_on();
         unsafe { ManuallyDrop::drop(&mut this.raw) };
-        debug_assert_eq!(result.result, sys::em_promise_result_t::EM_PROMISE_FULFILL);
-        return unsafe { Box::from_raw(result.value.cast::<T>()) };
+
+        // If the promise was polled, its `then` callback has already taken ownership of the value
+        return match this.polled.take() {
+            Some(polled) => polled
+                .result
+                .take()
+                .expect("promise settled without a value"),
+            None => unsafe { take_settled(result) },
+        };
+    }
+
+    /// Converts the promise into a callback result that makes the promise being settled adopt its state.
+    fn into_match(self) -> sys::em_settled_result_t {
+        let raw = match self.into_raw() {
+            Inner::Owned(raw) => raw,
+            Inner::Shared(raw) => match Rc::try_unwrap(raw) {
+                Ok(raw) => raw,
+                // The event still holds the handle, and will keep it alive until it settles the promise
+                Err(raw) => {
+                    return sys::em_settled_result_t {
+                        result: sys::em_promise_result_t::EM_PROMISE_MATCH,
+                        value: raw.inner.cast(),
+                    }
+                }
+            },
+        };
+
+        // Emscripten destroys the handle for us after matching it
+        let raw = ManuallyDrop::new(raw);
+        return sys::em_settled_result_t {
+            result: sys::em_promise_result_t::EM_PROMISE_MATCH_RELEASE,
+            value: raw.inner.cast(),
+        };
+    }
+}
+
+impl<T: 'static, E: 'static> Promise<T, E> {
+    /// Returns a promise that fulfills with the result of `f` applied to this promise's value.
+    ///
+    /// If this promise rejects, the returned one rejects with the same error.
+    pub fn then<U, F>(self, f: F) -> Promise<U, E>
+    where
+        F: 'static + FnOnce(T) -> U,
+    {
+        let raw = self.into_raw().then_settled(move |settled| unsafe {
+            match settled.result {
+                sys::em_promise_result_t::EM_PROMISE_FULFILL => {
+                    into_settled::<U, E>(Ok(f(*Box::from_raw(settled.value.cast::<T>()))))
+                }
+                _ => settled,
+            }
+        });
+        return unsafe { Promise::from_raw(Inner::Owned(raw)) };
+    }
+
+    /// Returns a promise that settles like the one returned by `f`, applied to this promise's value.
+    ///
+    /// If this promise rejects, the returned one rejects with the same error.
+    pub fn and_then<U, F>(self, f: F) -> Promise<U, E>
+    where
+        U: 'static,
+        F: 'static + FnOnce(T) -> Promise<U, E>,
+    {
+        let raw = self.into_raw().then_settled(move |settled| unsafe {
+            match settled.result {
+                sys::em_promise_result_t::EM_PROMISE_FULFILL => {
+                    f(*Box::from_raw(settled.value.cast::<T>())).into_match()
+                }
+                _ => settled,
+            }
+        });
+        return unsafe { Promise::from_raw(Inner::Owned(raw)) };
+    }
+
+    /// Returns a promise that fulfills with the values of all `promises`, in order, once all of them
+    /// have fulfilled. It rejects with the first error as soon as any of them rejects.
+    #[doc(alias = "emscripten_promise_all")]
+    pub fn all(promises: impl IntoIterator<Item = Self>) -> Promise<Vec<T>, E> {
+        return Self::combine(
+            promises,
+            |handles| unsafe {
+                let mut results = vec![std::ptr::null_mut(); handles.len()].into_boxed_slice();
+                let raw = sys::emscripten_promise_all(
+                    handles.as_mut_ptr(),
+                    results.as_mut_ptr(),
+                    handles.len(),
+                );
+                (raw, Box::new(results) as Box<dyn Any>)
+            },
+            |slots, settled| match settled.result {
+                sys::em_promise_result_t::EM_PROMISE_FULFILL => {
+                    Ok(slots.iter().filter_map(|slot| slot.take()?.ok()).collect())
+                }
+                _ => match Self::take_marked(slots, settled.value) {
+                    Err(e) => Err(e),
+                    Ok(_) => unreachable!("rejected with a fulfilled promise"),
+                },
+            },
+        );
+    }
+
+    /// Returns a promise that fulfills once all `promises` have settled, with their results in order.
+    #[doc(alias = "emscripten_promise_all_settled")]
+    pub fn all_settled(promises: impl IntoIterator<Item = Self>) -> Promise<Vec<Result<T, E>>> {
+        return Self::combine(
+            promises,
+            |handles| unsafe {
+                let mut results = (0..handles.len())
+                    .map(|_| sys::em_settled_result_t {
+                        result: sys::em_promise_result_t::EM_PROMISE_FULFILL,
+                        value: std::ptr::null_mut(),
+                    })
+                    .collect::<Box<[_]>>();
+                let raw = sys::emscripten_promise_all_settled(
+                    handles.as_mut_ptr(),
+                    results.as_mut_ptr(),
+                    handles.len(),
+                );
+                (raw, Box::new(results) as Box<dyn Any>)
+            },
+            |slots, _| {
+                Ok(slots
+                    .iter()
+                    .map(|slot| slot.take().expect("promise settled without a value"))
+                    .collect())
+            },
+        );
+    }
+
+    /// Returns a promise that fulfills with the value of the first of `promises` to fulfill. If all of
+    /// them reject, it rejects with all their errors, in order.
+    #[doc(alias = "emscripten_promise_any")]
+    pub fn any(promises: impl IntoIterator<Item = Self>) -> Promise<T, Vec<E>> {
+        return Self::combine(
+            promises,
+            |handles| unsafe {
+                let mut errors = vec![std::ptr::null_mut(); handles.len()].into_boxed_slice();
+                let raw = sys::emscripten_promise_any(
+                    handles.as_mut_ptr(),
+                    errors.as_mut_ptr(),
+                    handles.len(),
+                );
+                (raw, Box::new(errors) as Box<dyn Any>)
+            },
+            |slots, settled| match settled.result {
+                sys::em_promise_result_t::EM_PROMISE_FULFILL => {
+                    match Self::take_marked(slots, settled.value) {
+                        Ok(val) => Ok(val),
+                        Err(_) => unreachable!("fulfilled with a rejected promise"),
+                    }
+                }
+                _ => Err(slots.iter().filter_map(|slot| slot.take()?.err()).collect()),
+            },
+        );
+    }
+
+    /// Returns a promise that settles like the first of `promises` to settle.
+    #[doc(alias = "emscripten_promise_race")]
+    pub fn race(promises: impl IntoIterator<Item = Self>) -> Self {
+        return Self::combine(
+            promises,
+            |handles| unsafe {
+                let raw = sys::emscripten_promise_race(handles.as_mut_ptr(), handles.len());
+                (raw, Box::new(()) as Box<dyn Any>)
+            },
+            |slots, settled| Self::take_marked(slots, settled.value),
+        );
+    }
+
+    /// Combines `promises` with `combinator`, which receives their handles and returns the combined
+    /// promise alongside any storage it needs kept alive until it settles.
+    ///
+    /// Each result is moved into a slot as soon as its promise settles, so that results that don't make
+    /// it into the output (like the losers of a race) are still dropped.
+    fn combine<U: 'static, F: 'static>(
+        promises: impl IntoIterator<Item = Self>,
+        combinator: impl FnOnce(&mut [em_promise_t]) -> (em_promise_t, Box<dyn Any>),
+        finish: impl 'static
+            + FnOnce(&[Cell<Option<Result<T, E>>>], sys::em_settled_result_t) -> Result<U, F>,
+    ) -> Promise<U, F> {
+        let promises = promises.into_iter().map(Self::into_raw).collect::<Vec<_>>();
+        let slots = promises
+            .iter()
+            .map(|_| Cell::new(None))
+            .collect::<Rc<[Cell<Option<Result<T, E>>>]>>();
+
+        // Chained promises settle with their (one-based) index instead of their result
+        let inputs = promises
+            .iter()
+            .enumerate()
+            .map(|(i, raw)| {
+                let slots = slots.clone();
+                raw.then_settled(move |settled| unsafe {
+                    let result = settled.result;
+                    slots[i].set(Some(match take_settled::<T, E>(settled) {
+                        Ok(val) => Ok(*val),
+                        Err(e) => Err(*e),
+                    }));
+
+                    return sys::em_settled_result_t {
+                        result,
+                        value: (i + 1) as *mut c_void,
+                    };
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(promises);
+
+        let mut handles = inputs.iter().map(|raw| raw.inner).collect::<Vec<_>>();
+        let (raw, storage) = combinator(&mut handles);
+        drop(inputs);
+
+        let raw = RawPromise { inner: raw }.then_settled(move |settled| {
+            drop(storage);
+            return into_settled(finish(&slots, settled));
+        });
+        return unsafe { Promise::from_raw(Inner::Owned(raw)) };
+    }
+
+    fn take_marked(slots: &[Cell<Option<Result<T, E>>>], index: *mut c_void) -> Result<T, E> {
+        return slots[index as usize - 1]
+            .take()
+            .expect("promise settled without a value");
     }
 }
 
-impl<T> Drop for Promise<T> {
+impl<T, E> Future for Promise<T, E> {
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let polled = match this.polled {
+            Some(ref polled) => polled,
+            None => {
+                let polled = Rc::new(Polled {
+                    result: Cell::new(None),
+                    waker: Cell::new(None),
+                });
+
+                let settled_polled = polled.clone();
+                let _ = this.raw.then_settled(move |settled| {
+                    settled_polled
+                        .result
+                        .set(Some(unsafe { take_settled::<T, E>(settled) }));
+                    if let Some(waker) = settled_polled.waker.take() {
+                        waker.wake();
+                    }
+
+                    return sys::em_settled_result_t {
+                        result: sys::em_promise_result_t::EM_PROMISE_FULFILL,
+                        value: std::ptr::null_mut(),
+                    };
+                });
+
+                &*this.polled.insert(polled)
+            }
+        };
+
+        return match polled.result.take() {
+            Some(Ok(val)) => Poll::Ready(Ok(*val)),
+            Some(Err(e)) => Poll::Ready(Err(*e)),
+            None => {
+                polled.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        };
+    }
+}
+
+// The value is always boxed, so it's never pinned
+impl<T, E> Unpin for Promise<T, E> {}
+
+impl<T, E> Drop for Promise<T, E> {
     fn drop(&mut self) {
-        let _ = self.raw.then(move |data| unsafe {
-            drop(Box::from_raw(data.cast::<T>()));
+        // Once polled, the value is dropped alongside the `Polled` state instead
+        if self.polled.is_some() {
+            return unsafe { ManuallyDrop::drop(&mut self.raw) };
+        }
+
+        let _ = self.raw.then_settled(move |settled| unsafe {
+            drop(take_settled::<T, E>(settled));
             return sys::em_settled_result_t {
                 result: sys::em_promise_result_t::EM_PROMISE_FULFILL,
                 value: std::ptr::null_mut(),
@@ -339,9 +790,39 @@ impl<T> Drop for Promise<T> {
     }
 }
 
+/// Takes ownership of the value of a settled promise.
+///
+/// # Safety
+/// `settled` must be the fulfilled `Box<T>` or rejected `Box<E>` of a promise.
+unsafe fn take_settled<T, E>(settled: sys::em_settled_result_t) -> Result<Box<T>, Box<E>> {
+    return match settled.result {
+        sys::em_promise_result_t::EM_PROMISE_FULFILL => {
+            Ok(Box::from_raw(settled.value.cast::<T>()))
+        }
+        sys::em_promise_result_t::EM_PROMISE_REJECT => {
+            Err(Box::from_raw(settled.value.cast::<E>()))
+        }
+        // Matching is resolved by Emscripten before the promise settles
+        _ => unreachable!("promise settled with {:?}", settled.result),
+    };
+}
+
+fn into_settled<T, E>(result: Result<T, E>) -> sys::em_settled_result_t {
+    return match result {
+        Ok(val) => sys::em_settled_result_t {
+            result: sys::em_promise_result_t::EM_PROMISE_FULFILL,
+            value: Box::into_raw(Box::new(val)).cast(),
+        },
+        Err(e) => sys::em_settled_result_t {
+            result: sys::em_promise_result_t::EM_PROMISE_REJECT,
+            value: Box::into_raw(Box::new(e)).cast(),
+        },
+    };
+}
+
 enum Inner {
     Shared(Rc<RawPromise>),
-    // Owned(RawPromise),
+    Owned(RawPromise),
 }
 
 impl Deref for Inner {
@@ -351,7 +832,7 @@ impl Deref for Inner {
     fn deref(&self) -> &Self::Target {
         match self {
             Inner::Shared(raw) => raw,
-            // Inner::Owned(raw) => raw,
+            Inner::Owned(raw) => raw,
         }
     }
 }
@@ -368,17 +849,58 @@ impl RawPromise {
         };
     }
 
-    fn then<F>(&self, f: F) -> Self
+    /// Registers `f` to run once the promise settles either way, returning the promise it settles.
+    fn then_settled<F>(&self, f: F) -> Self
     where
-        F: 'static + FnOnce(*mut c_void) -> sys::em_settled_result_t,
+        F: FnOnce(sys::em_settled_result_t) -> sys::em_settled_result_t,
     {
-        unsafe extern "C" fn then<F: FnOnce(*mut c_void) -> sys::em_settled_result_t>(
+        unsafe extern "C" fn on_fulfilled<F>(
             result: *mut *mut c_void,
             data: *mut c_void,
             value: *mut c_void,
-        ) -> sys::em_promise_result_t {
+        ) -> sys::em_promise_result_t
+        where
+            F: FnOnce(sys::em_settled_result_t) -> sys::em_settled_result_t,
+        {
+            settle::<F>(
+                result,
+                data,
+                sys::em_promise_result_t::EM_PROMISE_FULFILL,
+                value,
+            )
+        }
+
+        unsafe extern "C" fn on_rejected<F>(
+            result: *mut *mut c_void,
+            data: *mut c_void,
+            value: *mut c_void,
+        ) -> sys::em_promise_result_t
+        where
+            F: FnOnce(sys::em_settled_result_t) -> sys::em_settled_result_t,
+        {
+            settle::<F>(
+                result,
+                data,
+                sys::em_promise_result_t::EM_PROMISE_REJECT,
+                value,
+            )
+        }
+
+        unsafe fn settle<F>(
+            result: *mut *mut c_void,
+            data: *mut c_void,
+            kind: sys::em_promise_result_t,
+            value: *mut c_void,
+        ) -> sys::em_promise_result_t
+        where
+            F: FnOnce(sys::em_settled_result_t) -> sys::em_settled_result_t,
+        {
+            // Only one of the callbacks ever runs, so the closure is taken exactly once
             let f = Box::from_raw(data.cast::<F>());
-            let settled_result = f(value);
+            let settled_result = f(sys::em_settled_result_t {
+                result: kind,
+                value,
+            });
 
             *result = settled_result.value;
             return settled_result.result;
@@ -389,8 +911,8 @@ impl RawPromise {
             inner: unsafe {
                 sys::emscripten_promise_then(
                     self.inner,
-                    Some(then::<F>),
-                    None,
+                    Some(on_fulfilled::<F>),
+                    Some(on_rejected::<F>),
                     Box::into_raw(data).cast(),
                 )
             },
@@ -399,17 +921,16 @@ impl RawPromise {
 
     #[inline]
     unsafe fn fulfill<T>(&self, val: T) {
-        self.fulfill_boxed(Box::new(val))
+        self.resolve_boxed(sys::em_promise_result_t::EM_PROMISE_FULFILL, Box::new(val))
     }
 
-    unsafe fn fulfill_boxed<T>(&self, val: Box<T>) {
-        unsafe {
-            sys::emscripten_promise_resolve(
-                self.inner,
-                sys::em_promise_result_t::EM_PROMISE_FULFILL,
-                Box::into_raw(val).cast(),
-            )
-        }
+    #[inline]
+    unsafe fn reject<E>(&self, err: E) {
+        self.resolve_boxed(sys::em_promise_result_t::EM_PROMISE_REJECT, Box::new(err))
+    }
+
+    unsafe fn resolve_boxed<T>(&self, result: sys::em_promise_result_t, val: Box<T>) {
+        unsafe { sys::emscripten_promise_resolve(self.inner, result, Box::into_raw(val).cast()) }
     }
 
     #[cfg(feature = "asyncify")]
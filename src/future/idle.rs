@@ -0,0 +1,186 @@
+use crate::time::Instant;
+use docfg::docfg;
+use pin_project::pin_project;
+use std::{
+    cell::Cell,
+    ffi::c_void,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+thread_local! {
+    /// End of the idle period the current thread is in, if any
+    static IDLE_DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Waits until the browser is idle, using `requestIdleCallback` (or `setTimeout` where it isn't available).
+///
+/// Where `requestIdleCallback` isn't available (like in workers), every timeout is treated as an idle period
+/// of 50ms. That budget is synthetic: the browser knows nothing about it, so it may well be busy meanwhile.
+#[doc(alias = "requestIdleCallback")]
+#[inline]
+pub fn idle() -> Idle {
+    Idle {
+        state: None,
+        #[cfg(feature = "proxying")]
+        task: None,
+    }
+}
+
+/// Returns how much of the current idle period is left, or zero if the thread isn't idle.
+///
+/// Tasks spawned with [`spawn_idle`] are polled within the idle period that wakes them, so they see its
+/// remaining time.
+pub fn time_remaining() -> Duration {
+    return match IDLE_DEADLINE.get() {
+        Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+        None => Duration::ZERO,
+    };
+}
+
+/// Spawns `fut` on the local runtime, polling it only during idle periods.
+///
+/// Whenever the task is woken outside of an idle period, it waits for the next one before being polled,
+/// so it never competes with rendering or input handling.
+#[docfg(feature = "proxying")]
+pub fn spawn_idle<Fut>(fut: Fut) -> super::JoinHandle<Fut::Output>
+where
+    Fut: 'static + Future,
+    Fut::Output: 'static,
+{
+    super::spawn_local(IdleTask { fut, idle: None })
+}
+
+/// An idle period, returned by [`idle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdleDeadline {
+    deadline: Instant,
+}
+
+impl IdleDeadline {
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    #[inline]
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    #[inline]
+    pub fn did_expire(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+/// Future returned by [`idle`].
+pub struct Idle {
+    state: Option<Rc<IdleState>>,
+    /// Local task to poll as soon as the idle period starts, set by [`spawn_idle`]
+    #[cfg(feature = "proxying")]
+    task: Option<u64>,
+}
+
+#[derive(Default)]
+struct IdleState {
+    deadline: Cell<Option<Instant>>,
+    waker: Cell<Option<Waker>>,
+    #[cfg(feature = "proxying")]
+    task: Option<u64>,
+}
+
+impl std::fmt::Debug for Idle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Idle").finish_non_exhaustive()
+    }
+}
+
+impl Future for Idle {
+    type Output = IdleDeadline;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        unsafe extern "C" fn onidle(time_remaining: f64, user_data: *mut c_void) {
+            let state = Rc::from_raw(user_data.cast_const().cast::<IdleState>());
+            let deadline = Instant::now() + Duration::from_secs_f64(time_remaining.max(0.) / 1000.);
+
+            IDLE_DEADLINE.set(Some(deadline));
+            state.deadline.set(Some(deadline));
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+
+            // Polls the idle task now, since the next tick would come after the idle period is over
+            #[cfg(feature = "proxying")]
+            if let Some(id) = state.task {
+                if Instant::now() < deadline {
+                    let _ = super::local::LOCAL_RUNTIME.try_with(|rt| rt.poll_now(id));
+                }
+            }
+            IDLE_DEADLINE.set(None);
+        }
+
+        #[cfg(feature = "proxying")]
+        let task = self.task;
+        let state = self.state.get_or_insert_with(|| {
+            let state = Rc::new(IdleState {
+                #[cfg(feature = "proxying")]
+                task,
+                ..Default::default()
+            });
+            unsafe {
+                sys::RequestIdle(Some(onidle), Rc::into_raw(state.clone()).cast_mut().cast());
+            }
+            state
+        });
+
+        if let Some(deadline) = state.deadline.get() {
+            return Poll::Ready(IdleDeadline { deadline });
+        }
+
+        state.waker.set(Some(cx.waker().clone()));
+        return Poll::Pending;
+    }
+}
+
+#[cfg(feature = "proxying")]
+#[pin_project]
+struct IdleTask<Fut> {
+    #[pin]
+    fut: Fut,
+    idle: Option<Idle>,
+}
+
+#[cfg(feature = "proxying")]
+impl<Fut: Future> Future for IdleTask<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if time_remaining().is_zero() {
+            let idle = this.idle.get_or_insert_with(|| Idle {
+                state: None,
+                task: super::local::LOCAL_RUNTIME.with(|rt| rt.current_task()),
+            });
+            if Pin::new(idle).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        *this.idle = None;
+        return this.fut.poll(cx);
+    }
+}
+
+mod sys {
+    #![allow(non_upper_case_globals)]
+    #![allow(non_camel_case_types)]
+    #![allow(non_snake_case)]
+    #![allow(dead_code)]
+
+    include!(concat!(env!("OUT_DIR"), "/idle.rs"));
+}
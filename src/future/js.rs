@@ -0,0 +1,138 @@
+use crate::value::JsValue;
+use docfg::docfg;
+use std::{
+    cell::Cell,
+    ffi::c_void,
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// A future that resolves when a JavaScript promise settles.
+///
+/// Unlike [`JsValue::block_on`], it doesn't block the current thread (or require asyncify), so it can be
+/// awaited inside [`spawn_local`](super::spawn_local) like any other future.
+pub struct JsFuture {
+    state: Rc<State>,
+}
+
+#[derive(Default)]
+struct State {
+    result: Cell<Option<Result<JsValue, JsValue>>>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl JsFuture {
+    /// Creates a future that resolves with the fulfilled value of `promise`, or its rejection reason as an error.
+    ///
+    /// If `promise` isn't a promise, the future resolves to it directly, like `await` would.
+    pub fn new(promise: &JsValue) -> Self {
+        unsafe extern "C" fn onsettled(
+            value: *mut c_void,
+            fulfilled: bool,
+            user_data: *mut c_void,
+        ) {
+            let state = Rc::from_raw(user_data.cast_const().cast::<State>());
+            let value = JsValue::take_ownership(value.cast());
+            state.result.set(Some(match fulfilled {
+                true => Ok(value),
+                false => Err(value),
+            }));
+
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+
+        let state = Rc::new(State::default());
+        unsafe {
+            sys::PromiseThen(
+                promise.as_handle().cast(),
+                Some(onsettled),
+                Rc::into_raw(state.clone()).cast_mut().cast(),
+            )
+        }
+
+        return Self { state };
+    }
+}
+
+impl Debug for JsFuture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsFuture").finish_non_exhaustive()
+    }
+}
+
+impl From<JsValue> for JsFuture {
+    #[inline]
+    fn from(promise: JsValue) -> Self {
+        Self::new(&promise)
+    }
+}
+
+impl Future for JsFuture {
+    type Output = Result<JsValue, JsValue>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.state.result.take() {
+            return Poll::Ready(result);
+        }
+
+        self.state.waker.set(Some(cx.waker().clone()));
+        return Poll::Pending;
+    }
+}
+
+/// Spawns `fut` on the local runtime, returning a JavaScript promise that settles with its output.
+///
+/// The promise is fulfilled with the [`Ok`] value, or rejected with the [`Err`] value. If `fut` panics,
+/// the promise is rejected with a string describing it.
+#[docfg(feature = "proxying")]
+pub fn future_to_promise<Fut>(fut: Fut) -> JsValue
+where
+    Fut: 'static + Future<Output = Result<JsValue, JsValue>>,
+{
+    use futures::FutureExt;
+    use std::panic::AssertUnwindSafe;
+
+    let mut resolve = std::ptr::null_mut();
+    let mut reject = std::ptr::null_mut();
+    let (promise, resolve, reject) = unsafe {
+        let promise = sys::PromiseWithResolvers(&mut resolve, &mut reject);
+        (
+            JsValue::take_ownership(promise.cast()),
+            JsValue::take_ownership(resolve.cast()),
+            JsValue::take_ownership(reject.cast()),
+        )
+    };
+
+    drop(super::spawn_local(async move {
+        let (settle, value) = match AssertUnwindSafe(fut).catch_unwind().await {
+            Ok(Ok(val)) => (resolve, val),
+            Ok(Err(e)) => (reject, e),
+            Err(payload) => {
+                let msg = super::panic_message(payload.as_ref()).replace('\0', "");
+                (
+                    reject,
+                    JsValue::from_str(format!("The Rust future panicked: {msg}"))
+                        .unwrap_or(JsValue::UNDEFINED),
+                )
+            }
+        };
+
+        unsafe { sys::PromiseSettle(settle.as_handle().cast(), value.as_handle().cast()) }
+    }));
+
+    return promise;
+}
+
+mod sys {
+    #![allow(non_upper_case_globals)]
+    #![allow(non_camel_case_types)]
+    #![allow(non_snake_case)]
+    #![allow(dead_code)]
+
+    include!(concat!(env!("OUT_DIR"), "/js_promise.rs"));
+}
@@ -0,0 +1,368 @@
+use super::JoinHandle;
+use crate::{
+    proxying::Queue,
+    sys::{self, PthreadWrapper},
+    time::Instant,
+};
+use futures::task::{FutureObj, LocalFutureObj, LocalSpawn, Spawn, SpawnError};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    ffi::c_void,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Waker},
+    time::Duration,
+};
+
+/// Default number of tasks polled per tick before yielding to the event loop.
+pub(crate) const DEFAULT_BUDGET: usize = 128;
+
+thread_local! {
+    pub(crate) static LOCAL_RUNTIME: Runtime = Runtime::new();
+}
+
+/// The single-threaded runtime behind [`spawn_local`](super::spawn_local).
+///
+/// Each tick runs as its own macrotask, and polls at most `budget` tasks before scheduling the next tick,
+/// so that long-running tasks can't starve the browser.
+pub(crate) struct Runtime {
+    tasks: RefCell<HashMap<u64, Task>>,
+    ready: RefCell<VecDeque<u64>>,
+    next_id: Cell<u64>,
+    tick_scheduled: Cell<bool>,
+    budget: Cell<usize>,
+    total_polls: Cell<u64>,
+    total_poll_time: Cell<Duration>,
+    /// The task being polled, if any, which is missing from `tasks` meanwhile
+    polling: Cell<Option<u64>>,
+}
+
+struct Task {
+    fut: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Arc<TaskWaker>,
+    name: Option<Arc<str>>,
+    spawned_at: Instant,
+    polls: u64,
+    poll_time: Duration,
+}
+
+/// A snapshot of the state of a local runtime, returned by [`local_metrics`](super::local_metrics).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RuntimeMetrics {
+    /// Number of tasks that haven't completed yet
+    pub live_tasks: usize,
+    /// Number of tasks waiting to be polled
+    pub ready_tasks: usize,
+    /// Number of tasks spawned since the runtime started
+    pub spawned_tasks: u64,
+    pub total_polls: u64,
+    pub total_poll_time: Duration,
+}
+
+/// A snapshot of the state of a task, returned by [`local_tasks`](super::local_tasks).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TaskMetrics {
+    pub id: u64,
+    pub name: Option<Arc<str>>,
+    /// Whether the task is waiting to be polled
+    pub is_ready: bool,
+    pub spawned_at: Instant,
+    pub polls: u64,
+    pub poll_time: Duration,
+}
+
+impl Runtime {
+    fn new() -> Self {
+        return Self {
+            tasks: RefCell::new(HashMap::new()),
+            ready: RefCell::new(VecDeque::new()),
+            next_id: Cell::new(0),
+            tick_scheduled: Cell::new(false),
+            budget: Cell::new(DEFAULT_BUDGET),
+            total_polls: Cell::new(0),
+            total_poll_time: Cell::new(Duration::ZERO),
+            polling: Cell::new(None),
+        };
+    }
+
+    pub fn spawn(&self, fut: impl 'static + Future<Output = ()>, name: Option<Arc<str>>) {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let waker = Arc::new(TaskWaker {
+            id,
+            thread: PthreadWrapper::current(),
+            scheduled: AtomicBool::new(true),
+        });
+        self.tasks.borrow_mut().insert(
+            id,
+            Task {
+                fut: Box::pin(fut),
+                waker,
+                name,
+                spawned_at: Instant::now(),
+                polls: 0,
+                poll_time: Duration::ZERO,
+            },
+        );
+        self.schedule(id);
+    }
+
+    /// The task currently being polled (if any) is counted as live, but isn't included in [`tasks`](Self::tasks).
+    pub fn metrics(&self) -> RuntimeMetrics {
+        return RuntimeMetrics {
+            live_tasks: self.tasks.borrow().len() + usize::from(self.polling.get().is_some()),
+            ready_tasks: self.ready.borrow().len(),
+            spawned_tasks: self.next_id.get(),
+            total_polls: self.total_polls.get(),
+            total_poll_time: self.total_poll_time.get(),
+        };
+    }
+
+    pub fn tasks(&self) -> Vec<TaskMetrics> {
+        let ready = self.ready.borrow();
+        let mut tasks = self
+            .tasks
+            .borrow()
+            .iter()
+            .map(|(&id, task)| TaskMetrics {
+                id,
+                name: task.name.clone(),
+                is_ready: ready.contains(&id),
+                spawned_at: task.spawned_at,
+                polls: task.polls,
+                poll_time: task.poll_time,
+            })
+            .collect::<Vec<_>>();
+
+        tasks.sort_unstable_by_key(|task| task.id);
+        return tasks;
+    }
+
+    #[inline]
+    pub fn set_budget(&self, budget: usize) {
+        self.budget.set(budget.max(1));
+    }
+
+    fn schedule(&self, id: u64) {
+        self.ready.borrow_mut().push_back(id);
+        self.schedule_tick();
+    }
+
+    fn schedule_tick(&self) {
+        unsafe extern "C" fn tick(_: *mut c_void) {
+            let _ = LOCAL_RUNTIME.try_with(Runtime::tick);
+        }
+
+        if !self.tick_scheduled.replace(true) {
+            unsafe { sys::emscripten_set_immediate(Some(tick), std::ptr::null_mut()) };
+        }
+    }
+
+    /// Returns the id of the task being polled, if any.
+    #[inline]
+    pub fn current_task(&self) -> Option<u64> {
+        self.polling.get()
+    }
+
+    /// Polls task `id` right away if it's waiting to be polled, instead of waiting for the next tick.
+    ///
+    /// Does nothing if called from inside a task, in which case `id` is polled on the next tick as usual.
+    pub fn poll_now(&self, id: u64) {
+        if self.polling.get().is_some() {
+            return;
+        }
+
+        let mut ready = self.ready.borrow_mut();
+        let Some(pos) = ready.iter().position(|&ready| ready == id) else {
+            return;
+        };
+        ready.remove(pos);
+        drop(ready);
+
+        self.poll_task(id);
+    }
+
+    fn tick(&self) {
+        self.tick_scheduled.set(false);
+
+        for _ in 0..self.budget.get() {
+            let Some(id) = self.ready.borrow_mut().pop_front() else {
+                return;
+            };
+            self.poll_task(id);
+        }
+
+        // Out of budget, so we let the browser run before polling the rest
+        if !self.ready.borrow().is_empty() {
+            self.schedule_tick();
+        }
+    }
+
+    fn poll_task(&self, id: u64) {
+        // The task is taken out while it's polled, so that it can spawn other tasks
+        let Some(mut task) = self.tasks.borrow_mut().remove(&id) else {
+            return;
+        };
+
+        task.waker.scheduled.store(false, Ordering::Release);
+        let waker = Waker::from(task.waker.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        self.polling.set(Some(id));
+        let start = Instant::now();
+        let poll = task.fut.as_mut().poll(&mut cx);
+        let elapsed = start.elapsed();
+        self.polling.set(None);
+
+        task.polls += 1;
+        task.poll_time += elapsed;
+        self.total_polls.set(self.total_polls.get() + 1);
+        self.total_poll_time
+            .set(self.total_poll_time.get() + elapsed);
+
+        if poll.is_pending() {
+            self.tasks.borrow_mut().insert(id, task);
+        }
+    }
+}
+
+struct TaskWaker {
+    id: u64,
+    thread: PthreadWrapper,
+    scheduled: AtomicBool,
+}
+
+impl std::task::Wake for TaskWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let id = self.id;
+        if self.thread.is_current() {
+            let _ = LOCAL_RUNTIME.try_with(|rt| rt.schedule(id));
+        } else {
+            // If the thread has exited, so has its runtime
+            Queue::system().proxy(&self.thread, move || {
+                let _ = LOCAL_RUNTIME.try_with(|rt| rt.schedule(id));
+            });
+        }
+    }
+}
+
+/// A handle to the local runtime of the thread that created it, which [`spawn_local`](super::spawn_local)
+/// spawns onto.
+///
+/// It implements [`LocalSpawn`] (and [`Spawn`]), so it can be given to libraries that are generic over
+/// their executor. Use [`remote`](LocalRuntimeHandle::remote) to get a handle that can spawn from
+/// other threads.
+#[derive(Debug, Clone)]
+pub struct LocalRuntimeHandle {
+    thread: PthreadWrapper,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl LocalRuntimeHandle {
+    /// Returns a handle to the local runtime of the current thread.
+    #[inline]
+    pub fn current() -> Self {
+        return Self {
+            thread: PthreadWrapper::current(),
+            _not_send: PhantomData,
+        };
+    }
+
+    #[inline]
+    pub fn spawn<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Future,
+        Fut::Output: 'static,
+    {
+        super::spawn_local(fut)
+    }
+
+    #[inline]
+    pub fn metrics(&self) -> RuntimeMetrics {
+        LOCAL_RUNTIME.with(Runtime::metrics)
+    }
+
+    /// Returns a handle to the same runtime, that can be sent to other threads.
+    #[inline]
+    pub fn remote(&self) -> RemoteRuntimeHandle {
+        RemoteRuntimeHandle {
+            thread: self.thread,
+        }
+    }
+}
+
+impl LocalSpawn for LocalRuntimeHandle {
+    #[inline]
+    fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
+        drop(super::spawn_local(future));
+        return Ok(());
+    }
+}
+
+impl Spawn for LocalRuntimeHandle {
+    #[inline]
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        drop(super::spawn_local(future));
+        return Ok(());
+    }
+}
+
+/// A handle to the local runtime of a thread, that can spawn tasks on it from any thread.
+///
+/// Futures are sent to the owning thread through [`proxying`](crate::proxying), and spawned there as if
+/// by [`spawn_local`](super::spawn_local).
+#[derive(Debug, Clone)]
+pub struct RemoteRuntimeHandle {
+    thread: PthreadWrapper,
+}
+
+impl RemoteRuntimeHandle {
+    /// Spawns the future returned by `f` on the runtime, like [`spawn_on`](super::spawn_on).
+    #[inline]
+    pub fn spawn_with<F, Fut>(&self, f: F) -> JoinHandle<Fut::Output>
+    where
+        F: 'static + Send + FnOnce() -> Fut,
+        Fut: 'static + Future,
+        Fut::Output: 'static + Send,
+    {
+        super::spawn_on(&self.thread, f)
+    }
+
+    /// Returns `true` if the runtime belongs to the current thread.
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        self.thread.is_current()
+    }
+}
+
+impl Spawn for RemoteRuntimeHandle {
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        if self.thread.is_current() {
+            drop(super::spawn_local(future));
+            return Ok(());
+        }
+
+        return match Queue::system().proxy(&self.thread, move || drop(super::spawn_local(future))) {
+            true => Ok(()),
+            false => Err(SpawnError::shutdown()),
+        };
+    }
+}
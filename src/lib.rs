@@ -27,6 +27,9 @@ pub extern crate std as libstd;
 #[cfg(feature = "fetch")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
 pub mod fetch;
+#[cfg(feature = "proxying")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proxying")))]
+pub mod channel;
 pub mod future;
 #[cfg(feature = "proxying")]
 #[cfg_attr(docsrs, doc(cfg(feature = "proxying")))]
@@ -41,9 +44,18 @@ pub mod console;
 #[cfg(feature = "html")]
 #[cfg_attr(docsrs, doc(cfg(feature = "html")))]
 pub mod html;
-// #[cfg(all(feature = "asyncify", feature = "proxying"))]
-// #[cfg_attr(docsrs, doc(cfg(all(feature = "asyncify", feature = "proxying"))))]
-// pub mod sync;
+#[cfg(feature = "proxying")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proxying")))]
+pub mod main_thread;
+#[cfg(feature = "proxying")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proxying")))]
+pub mod runtime;
+pub mod sync;
+pub mod task;
+#[cfg(feature = "proxying")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proxying")))]
+pub mod thread;
+pub mod time;
 pub mod utils;
 pub mod value;
 pub mod wget;
@@ -164,7 +176,7 @@ pub fn set_finite_main_loop<F: FnMut()>(f: F, timing: Option<Timing>) {
                     Some(main_loop::<F>),
                     addr_of_mut!(*info).cast(),
                 );
-                promise.block_on();
+                promise.block_on().unwrap_or_else(|e| match e {});
 
                 if let Some(payload) = info.panic {
                     resume_unwind(payload);
@@ -222,7 +234,7 @@ pub fn set_finite_main_loop<F: FnMut()>(f: F, timing: Option<Timing>) {
                     Some(main_loop::<F>),
                     addr_of_mut!(*info).cast(),
                 );
-                promise.block_on();
+                promise.block_on().unwrap_or_else(|e| match e {});
 
                 if let Some(payload) = info.panic {
                     resume_unwind(payload);
@@ -265,7 +277,7 @@ pub fn set_finite_main_loop<F: FnMut()>(f: F, timing: Option<Timing>) {
                     dur.as_millis() as f64,
                     addr_of_mut!(*info).cast(),
                 );
-                promise.block_on();
+                promise.block_on().unwrap_or_else(|e| match e {});
 
                 if let Some(payload) = info.panic {
                     resume_unwind(payload);
@@ -308,7 +320,7 @@ pub fn set_finite_main_loop<F: FnMut()>(f: F, timing: Option<Timing>) {
                     Some(main_loop::<F>),
                     addr_of_mut!(*info).cast(),
                 );
-                promise.block_on();
+                promise.block_on().unwrap_or_else(|e| match e {});
 
                 if let Some(payload) = info.panic {
                     resume_unwind(payload);
@@ -458,7 +470,7 @@ pub mod sys {
 
     include!(concat!(env!("OUT_DIR"), "/emscripten.rs"));
 
-    #[derive(Clone, Copy)]
+    #[derive(Debug, Clone, Copy)]
     #[repr(transparent)]
     pub(crate) struct PthreadWrapper(pub pthread_t);
 
@@ -466,6 +478,16 @@ pub mod sys {
         pub fn current() -> Self {
             return Self(unsafe { libc::pthread_self() });
         }
+
+        #[cfg(feature = "proxying")]
+        pub fn main() -> Self {
+            return Self(unsafe { emscripten_main_runtime_thread_id() });
+        }
+
+        #[inline]
+        pub fn is_current(&self) -> bool {
+            return self.0 == unsafe { libc::pthread_self() };
+        }
     }
 
     impl JoinHandleExt for PthreadWrapper {
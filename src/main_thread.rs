@@ -0,0 +1,81 @@
+use crate::{
+    proxying::{proxy_async, Queue},
+    sys::PthreadWrapper,
+};
+use std::{
+    future::Future,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+};
+
+/// Returns `true` if called from the main runtime thread, where the DOM lives.
+#[doc(alias = "emscripten_is_main_runtime_thread")]
+#[inline]
+pub fn is_main_thread() -> bool {
+    PthreadWrapper::main().is_current()
+}
+
+/// Runs `f` on the main runtime thread, blocking the current thread until it completes.
+///
+/// If called from the main runtime thread, `f` is run immediately.
+///
+/// # Panics
+/// If `f` panics, the panic is caught on the main runtime thread and resumed on the current one.
+/// Also panics if the main runtime thread exited before completing `f`.
+#[doc(alias = "emscripten_proxy_sync")]
+pub fn run<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    let result = Queue::system()
+        .proxy_blocking(&PthreadWrapper::main(), move || {
+            catch_unwind(AssertUnwindSafe(f))
+        })
+        .expect("The main runtime thread didn't complete the closure");
+
+    return match result {
+        Ok(val) => val,
+        Err(payload) => resume_unwind(payload),
+    };
+}
+
+/// Runs `f` on the main runtime thread, without blocking the current one.
+///
+/// `f` only starts running once the returned future is polled, even when called from the main runtime thread.
+///
+/// # Panics
+/// If `f` panics, the panic is caught on the main runtime thread and resumed when the returned future is
+/// polled. The future also panics if the main runtime thread exited before completing `f`.
+pub fn run_async<F, T>(f: F) -> impl 'static + Future<Output = T>
+where
+    F: 'static + FnOnce() -> T + Send,
+    T: 'static + Send,
+{
+    let result = proxy_async(&PthreadWrapper::main(), move || {
+        catch_unwind(AssertUnwindSafe(f))
+    });
+    return async move {
+        match result
+            .await
+            .expect("The main runtime thread didn't complete the closure")
+        {
+            Ok(val) => val,
+            Err(payload) => resume_unwind(payload),
+        }
+    };
+}
+
+/// Schedules `f` to run on the main runtime thread, without waiting for it.
+///
+/// # Panics
+/// Panics if `f` couldn't be scheduled.
+#[doc(alias = "emscripten_proxy_async")]
+pub fn spawn<F>(f: F)
+where
+    F: 'static + FnOnce() + Send,
+{
+    assert!(
+        Queue::system().proxy(&PthreadWrapper::main(), f),
+        "Error scheduling closure on the main runtime thread"
+    );
+}
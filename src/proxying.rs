@@ -1,6 +1,19 @@
-use crate::sys;
+use crate::{
+    channel::oneshot,
+    sys::{self, PthreadWrapper},
+};
 use std::{
-    ffi::c_void, marker::PhantomData, mem::transmute, os::unix::thread::JoinHandleExt, sync::Arc,
+    cell::RefCell,
+    collections::HashMap,
+    ffi::c_void,
+    fmt::Debug,
+    future::Future,
+    marker::PhantomData,
+    mem::transmute,
+    os::unix::thread::{JoinHandleExt, RawPthread},
+    pin::Pin,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+    task::{Poll, Waker},
 };
 use utils_atomics::TakeCell;
 
@@ -67,6 +80,11 @@ impl<'a> Queue<'a> {
         }
     }
 
+    /// Runs `f` on `target_thread`, blocking the current thread until it completes.
+    ///
+    /// Returns `None` if `target_thread` couldn't run `f`. If called from `target_thread` itself, `f` is run
+    /// immediately.
+    #[doc(alias = "emscripten_proxy_sync")]
     pub fn proxy_blocking<F, T>(&self, target_thread: &impl JoinHandleExt, f: F) -> Option<T>
     where
         F: FnOnce() -> T + Send,
@@ -98,7 +116,7 @@ impl<'a> Queue<'a> {
                 target_thread,
                 Some(proxy::<F, T>),
                 arg.cast(),
-            ) == 0;
+            ) != 0;
 
             let arg = Box::from_raw(arg);
             if result {
@@ -109,6 +127,76 @@ impl<'a> Queue<'a> {
         }
     }
 
+    /// Runs `f` on `target_thread`, resolving to its result once it completes there, without blocking the
+    /// current thread.
+    ///
+    /// Resolves to [`Cancelled`] if `target_thread` exits before running `f`, or if it couldn't be reached.
+    /// If called from `target_thread` itself, `f` is run immediately.
+    ///
+    /// # Deadlocks
+    /// The result of `f` is sent straight from `target_thread`, but cancellation is reported back through
+    /// this queue. For queues other than the [system queue](Queue::system), which is executed automatically,
+    /// the returned future only resolves to [`Cancelled`] once the current thread executes the queue.
+    #[doc(alias = "emscripten_proxy_callback")]
+    pub fn call<F, T>(&self, target_thread: &impl JoinHandleExt, f: F) -> Call<T>
+    where
+        F: 'a + FnOnce() -> T + Send,
+        T: 'a + Send,
+    {
+        struct Inner<F, T> {
+            f: Option<F>,
+            send: Option<oneshot::Sender<Result<T, Cancelled>>>,
+        }
+
+        // Sends the result from the target thread, so that it doesn't wait for the caller to execute the queue
+        unsafe extern "C" fn proxy<F: FnOnce() -> T, T>(arg: *mut c_void) {
+            let this = &mut *arg.cast::<Inner<F, T>>();
+            let result = (this.f.take().unwrap_unchecked())();
+            let _ = this.send.take().unwrap_unchecked().send(Ok(result));
+        }
+
+        unsafe extern "C" fn callback<F, T>(arg: *mut c_void) {
+            drop(Box::from_raw(arg.cast::<Inner<F, T>>()));
+        }
+
+        unsafe extern "C" fn cancel<F, T>(arg: *mut c_void) {
+            let this = Box::from_raw(arg.cast::<Inner<F, T>>());
+            if let Some(send) = this.send {
+                let _ = send.send(Err(Cancelled));
+            }
+        }
+
+        let (send, recv) = oneshot::channel();
+        let target_thread = target_thread.as_pthread_t();
+        if target_thread == unsafe { libc::pthread_self() } {
+            let _ = send.send(Ok(f()));
+            return Call { recv };
+        }
+
+        let arg = Box::into_raw(Box::new(Inner {
+            f: Some(f),
+            send: Some(send),
+        }));
+
+        unsafe {
+            if sys::emscripten_proxy_callback(
+                self.inner,
+                transmute::<std::os::unix::thread::RawPthread, sys::pthread_t>(target_thread),
+                Some(proxy::<F, T>),
+                Some(callback::<F, T>),
+                Some(cancel::<F, T>),
+                arg.cast(),
+            ) == 0
+            {
+                if let Some(send) = Box::from_raw(arg).send {
+                    let _ = send.send(Err(Cancelled));
+                }
+            }
+        }
+
+        return Call { recv };
+    }
+
     pub fn proxy_blocking_with_ctx<F>(&self, target_thread: &impl JoinHandleExt, f: F) -> bool
     where
         F: FnOnce(Context) + Send,
@@ -117,10 +205,7 @@ impl<'a> Queue<'a> {
             ctx: *mut sys::em_proxying_ctx,
             arg: *mut c_void,
         ) {
-            Box::from_raw(arg.cast::<F>())(Context {
-                inner: ctx,
-                _phtm: PhantomData,
-            });
+            Box::from_raw(arg.cast::<F>())(Context { inner: ctx });
         }
 
         let arg = Box::into_raw(Box::new(f));
@@ -136,6 +221,54 @@ impl<'a> Queue<'a> {
         }
     }
 
+    /// Runs the future returned by `f` on `target_thread`'s local runtime, resolving to its output once it
+    /// completes there, without blocking the current thread.
+    ///
+    /// The proxied work is only finished once the future completes. Resolves to [`Cancelled`] if
+    /// `target_thread` exits before then, or if it couldn't be reached.
+    #[doc(alias = "emscripten_proxy_callback_with_ctx")]
+    pub fn proxy_with_ctx_async<F, Fut>(
+        &self,
+        target_thread: &impl JoinHandleExt,
+        f: F,
+    ) -> Call<Fut::Output>
+    where
+        F: 'a + FnOnce() -> Fut + Send,
+        Fut: 'static + Future,
+        Fut::Output: 'static + Send,
+    {
+        let (send, recv) = oneshot::channel();
+        let target_thread = target_thread.as_pthread_t();
+        if target_thread == unsafe { libc::pthread_self() } {
+            let fut = f();
+            drop(crate::future::spawn_local(async move {
+                let _ = send.send(Ok(fut.await));
+            }));
+            return Call { recv };
+        }
+
+        // The output is sent before the context is finished, so it's always there by the time `callback` runs
+        let (output_send, mut output_recv) = oneshot::channel();
+        self.proxy_callback_with_ctx(
+            &PthreadWrapper(target_thread),
+            with_callback(
+                move |ctx: Context| {
+                    let fut = f();
+                    drop(crate::future::spawn_local(async move {
+                        let _ = output_send.send(fut.await);
+                        ctx.finish();
+                    }));
+                },
+                move || {
+                    let _ = send.send(output_recv.try_recv().map_err(|_| Cancelled));
+                },
+            ),
+        );
+
+        // If the work is cancelled, or couldn't be proxied, `send` is dropped and the call resolves to `Cancelled`
+        return Call { recv };
+    }
+
     pub fn proxy_callback<C>(&self, target_thread: &impl JoinHandleExt, cb: C) -> bool
     where
         C: IntoCallback<Callback: 'a>,
@@ -210,10 +343,10 @@ impl<'a> Queue<'a> {
             arg: *mut c_void,
         ) {
             let this = Arc::from_raw(arg as *const Inner<C>);
-            this.cb.try_take().unwrap_unchecked().call(Context {
-                inner: ctx,
-                _phtm: PhantomData,
-            });
+            this.cb
+                .try_take()
+                .unwrap_unchecked()
+                .call(Context { inner: ctx });
         }
 
         unsafe extern "C" fn callback<C: CallbackWithCtx>(arg: *mut c_void) {
@@ -256,6 +389,19 @@ impl<'a> Queue<'a> {
     }
 }
 
+impl Queue<'static> {
+    /// Returns the system queue, which every thread drains automatically when it returns to the event loop, so
+    /// there's no need to [`execute`](Queue::execute) it manually.
+    #[doc(alias = "emscripten_proxy_get_system_queue")]
+    pub fn system() -> &'static Queue<'static> {
+        static SYSTEM: OnceLock<Queue<'static>> = OnceLock::new();
+        return SYSTEM.get_or_init(|| Queue {
+            inner: unsafe { sys::emscripten_proxy_get_system_queue() },
+            _phtm: PhantomData,
+        });
+    }
+}
+
 impl Drop for Queue<'_> {
     #[inline]
     fn drop(&mut self) {
@@ -266,12 +412,281 @@ impl Drop for Queue<'_> {
 unsafe impl Sync for Queue<'_> {}
 unsafe impl Send for Queue<'_> {}
 
+/// Future returned by [`Queue::call`].
+#[derive(Debug)]
+pub struct Call<T> {
+    recv: oneshot::Receiver<Result<T, Cancelled>>,
+}
+
+impl<T> Future for Call<T> {
+    type Output = Result<T, Cancelled>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        return Pin::new(&mut self.recv)
+            .poll(cx)
+            .map(|res| res.unwrap_or(Err(Cancelled)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("The target thread exited before running the proxied work")]
+pub struct Cancelled;
+
+/// Runs `f` on `thread`, resolving to its result, or to `None` if the thread couldn't run it.
+///
+/// Like any future, nothing happens until it's polled, no matter which thread `thread` is.
+pub(crate) fn proxy_async<F, R>(
+    thread: &PthreadWrapper,
+    f: F,
+) -> impl 'static + Future<Output = Option<R>>
+where
+    F: 'static + FnOnce() -> R + Send,
+    R: 'static + Send,
+{
+    let thread = *thread;
+    return async move {
+        if thread.is_current() {
+            return Some(f());
+        }
+
+        let (send, recv) = oneshot::channel();
+        Queue::system().proxy(&thread, move || {
+            let _ = send.send(f());
+        });
+        recv.await.ok()
+    };
+}
+
+/// Returns a [`Waker`] that always wakes `waker` on the current thread, proxying the wakeup to it when woken from a different one.
+pub fn thread_waker(waker: &Waker) -> Waker {
+    struct Wake {
+        waker: Waker,
+        thread: PthreadWrapper,
+    }
+
+    impl std::task::Wake for Wake {
+        #[inline]
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref()
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            if self.thread.is_current() {
+                return self.waker.wake_by_ref();
+            }
+
+            let waker = self.waker.clone();
+            if !Queue::system().proxy(&self.thread, move || waker.wake()) {
+                // The target thread can't be reached anymore, so wake it from here instead.
+                self.waker.wake_by_ref();
+            }
+        }
+    }
+
+    return Waker::from(Arc::new(Wake {
+        waker: waker.clone(),
+        thread: PthreadWrapper::current(),
+    }));
+}
+
+fn registry() -> &'static RwLock<HashMap<Arc<str>, NamedThread>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<Arc<str>, NamedThread>>> = OnceLock::new();
+    return REGISTRY.get_or_init(Default::default);
+}
+
+thread_local! {
+    static REGISTERED: Registered = const { Registered(RefCell::new(Vec::new())) };
+}
+
+/// Names registered by the current thread, which are unregistered when it exits.
+struct Registered(RefCell<Vec<Arc<str>>>);
+
+impl Registered {
+    fn unregister_all(&self) {
+        let mut registry = registry().write().unwrap_or_else(PoisonError::into_inner);
+        for name in self.0.take() {
+            if registry
+                .get(&name)
+                .is_some_and(|thread| thread.thread.is_current())
+            {
+                registry.remove(&name);
+            }
+        }
+    }
+}
+
+impl Drop for Registered {
+    #[inline]
+    fn drop(&mut self) {
+        self.unregister_all()
+    }
+}
+
+/// Unregisters every name the current thread registered, ahead of its exit.
+pub(crate) fn unregister_current() {
+    let _ = REGISTERED.try_with(Registered::unregister_all);
+}
+
+/// Registers the current thread under `name`, so that work can be proxied to it via [`lookup`], using the
+/// [system queue](Queue::system).
+///
+/// The thread is unregistered when it exits. Registering a name that's already taken replaces the previous entry.
+#[inline]
+pub fn register(name: impl Into<Arc<str>>) {
+    register_inner(name.into(), None)
+}
+
+/// Registers the current thread under `name`, so that work can be proxied to it via [`lookup`], using `queue`.
+///
+/// The current thread is responsible for [`execute`](Queue::execute)-ing `queue`. The thread is unregistered
+/// when it exits. Registering a name that's already taken replaces the previous entry.
+#[inline]
+pub fn register_with_queue(name: impl Into<Arc<str>>, queue: Arc<Queue<'static>>) {
+    register_inner(name.into(), Some(queue))
+}
+
+fn register_inner(name: Arc<str>, queue: Option<Arc<Queue<'static>>>) {
+    insert_registered(name.clone(), PthreadWrapper::current(), queue);
+    unregister_on_exit(name);
+}
+
+pub(crate) fn insert_registered(
+    name: Arc<str>,
+    thread: PthreadWrapper,
+    queue: Option<Arc<Queue<'static>>>,
+) {
+    let thread = NamedThread {
+        name: name.clone(),
+        thread,
+        queue,
+    };
+
+    registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name, thread);
+}
+
+/// Unregisters `name` when the current thread exits, if it's still registered to it by then.
+pub(crate) fn unregister_on_exit(name: Arc<str>) {
+    REGISTERED.with(|registered| registered.0.borrow_mut().push(name));
+}
+
+/// Removes the thread registered under `name`, returning it.
+pub fn unregister(name: &str) -> Option<NamedThread> {
+    return registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(name);
+}
+
+/// Returns the thread registered under `name`, if any.
+pub fn lookup(name: &str) -> Option<NamedThread> {
+    return registry()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .cloned();
+}
+
+/// A thread registered by name, alongside the queue to proxy work to it with.
+///
+/// It can be used as the target of any [`Queue`] method, or proxy work on its own queue directly.
+#[derive(Clone)]
+pub struct NamedThread {
+    name: Arc<str>,
+    thread: PthreadWrapper,
+    queue: Option<Arc<Queue<'static>>>,
+}
+
+impl NamedThread {
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the queue the thread was registered with, which is the [system queue](Queue::system) by default.
+    #[inline]
+    pub fn queue(&self) -> &Queue<'static> {
+        self.queue.as_deref().unwrap_or(Queue::system())
+    }
+
+    /// Schedules `f` to run on the thread, without waiting for it. See [`Queue::proxy`].
+    #[inline]
+    pub fn proxy<F>(&self, f: F) -> bool
+    where
+        F: 'static + FnOnce() + Send,
+    {
+        self.queue().proxy(self, f)
+    }
+
+    /// Runs `f` on the thread, blocking the current one until it completes. See [`Queue::proxy_blocking`].
+    #[inline]
+    pub fn proxy_blocking<F, T>(&self, f: F) -> Option<T>
+    where
+        F: FnOnce() -> T + Send,
+        T: Send,
+    {
+        self.queue().proxy_blocking(self, f)
+    }
+
+    /// Runs `f` on the thread, resolving to its result once it completes. See [`Queue::call`].
+    ///
+    /// # Deadlocks
+    /// If the thread was registered with its own queue, the returned future only resolves to [`Cancelled`]
+    /// once the current thread executes that queue.
+    #[inline]
+    pub fn call<F, T>(&self, f: F) -> Call<T>
+    where
+        F: 'static + FnOnce() -> T + Send,
+        T: 'static + Send,
+    {
+        self.queue().call(self, f)
+    }
+}
+
+impl JoinHandleExt for NamedThread {
+    #[inline]
+    fn as_pthread_t(&self) -> RawPthread {
+        self.thread.0
+    }
+
+    #[inline]
+    fn into_pthread_t(self) -> RawPthread {
+        self.thread.0
+    }
+}
+
+impl Debug for NamedThread {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamedThread")
+            .field("name", &self.name)
+            .field("thread", &self.thread.0)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Types that can be split into work proxied to another thread with [`Queue::proxy_callback`], and the
+/// [`Receiver`](Callback::Receiver) notified back on the current thread once it's done.
+///
+/// This is implemented for:
+/// - `(callback, receiver)` pairs of any custom [`Callback`].
+/// - [`with_callback(f, g)`](with_callback), where `f: FnOnce() -> T` runs on the target thread and `g: FnOnce(T)`
+/// receives its result on the current one. If the work is cancelled, `g` is dropped without being called.
+/// - [`with_sender(f, sender)`](with_sender), where the result of `f: FnOnce() -> T` is sent through a
+/// [`oneshot::Sender<T>`]. If the work is cancelled, the sender is dropped, closing the channel.
 pub trait IntoCallback {
     type Callback: Callback;
 
     fn into_callback(self) -> (Self::Callback, <Self::Callback as Callback>::Receiver);
 }
 
+/// Work proxied to another thread with [`Queue::proxy_callback`].
+///
+/// [`call`](Callback::call) runs on the target thread. Afterwards, exactly one of [`callback`](Callback::callback)
+/// or [`cancel`](Callback::cancel) runs on the thread that proxied the work, the next time it executes the queue:
+/// `callback` if `call` completed, and `cancel` if the target thread exited before it could run.
 pub trait Callback: Send {
     type Receiver;
 
@@ -280,6 +695,14 @@ pub trait Callback: Send {
     fn cancel(this: Self::Receiver);
 }
 
+/// Types that can be split into work proxied to another thread with [`Queue::proxy_callback_with_ctx`], and the
+/// [`Receiver`](CallbackWithCtx::Receiver) notified back on the current thread once it's done.
+///
+/// This is implemented for:
+/// - `(callback, receiver)` pairs of any custom [`CallbackWithCtx`].
+/// - [`with_callback(f, g)`](with_callback), where `f: FnOnce(Context)` runs on the target thread and
+/// `g: FnOnce()` runs on the current one once the context has been finished. If the work is cancelled, `g` is
+/// dropped without being called.
 pub trait IntoCallbackWithCtx {
     type Callback: CallbackWithCtx;
 
@@ -291,6 +714,11 @@ pub trait IntoCallbackWithCtx {
     );
 }
 
+/// Work proxied to another thread with [`Queue::proxy_callback_with_ctx`].
+///
+/// [`call`](CallbackWithCtx::call) runs on the target thread, and the work is considered done once its [`Context`]
+/// is finished. Afterwards, exactly one of [`callback`](CallbackWithCtx::callback) or
+/// [`cancel`](CallbackWithCtx::cancel) runs on the thread that proxied the work, the next time it executes the queue.
 pub trait CallbackWithCtx: Send {
     type Receiver;
 
@@ -322,19 +750,187 @@ impl<C: CallbackWithCtx> IntoCallbackWithCtx for (C, C::Receiver) {
     }
 }
 
-pub struct Context<'a> {
+/// Pairs the work `f` with the callback `g`, which runs on the current thread once `f` is done.
+/// See [`IntoCallback`] and [`IntoCallbackWithCtx`].
+#[inline]
+pub fn with_callback<F, G>(f: F, g: G) -> WithCallback<F, G> {
+    WithCallback { f, g }
+}
+
+/// Pairs the work `f` with `sender`, which receives its result. See [`IntoCallback`].
+#[inline]
+pub fn with_sender<F, T>(f: F, sender: oneshot::Sender<T>) -> SendCallback<F, T> {
+    SendCallback { f, send: sender }
+}
+
+/// Work paired with a callback, returned by [`with_callback`].
+pub struct WithCallback<F, G> {
+    f: F,
+    g: G,
+}
+
+/// [`Callback`] for [`with_callback`].
+pub struct FnCallback<F, T> {
+    f: F,
+    send: oneshot::Sender<T>,
+}
+
+/// Receiver of [`FnCallback`], calling back with the result of the work.
+pub struct FnReceiver<T> {
+    g: Box<dyn FnOnce(T)>,
+    recv: oneshot::Receiver<T>,
+}
+
+/// [`CallbackWithCtx`] for [`with_callback`].
+pub struct FnCallbackWithCtx<F> {
+    f: F,
+}
+
+/// [`Callback`] for [`with_sender`].
+pub struct SendCallback<F, T> {
+    f: F,
+    send: oneshot::Sender<T>,
+}
+
+impl<F, G, T> IntoCallback for WithCallback<F, G>
+where
+    F: FnOnce() -> T + Send,
+    G: 'static + FnOnce(T),
+    T: Send,
+{
+    type Callback = FnCallback<F, T>;
+
+    #[inline]
+    fn into_callback(self) -> (Self::Callback, <Self::Callback as Callback>::Receiver) {
+        let (send, recv) = oneshot::channel();
+        return (
+            FnCallback { f: self.f, send },
+            FnReceiver {
+                g: Box::new(self.g),
+                recv,
+            },
+        );
+    }
+}
+
+impl<F, T> Callback for FnCallback<F, T>
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    type Receiver = FnReceiver<T>;
+
+    #[inline]
+    fn call(self) {
+        let _ = self.send.send((self.f)());
+    }
+
+    #[inline]
+    fn callback(mut this: Self::Receiver) {
+        if let Ok(val) = this.recv.try_recv() {
+            (this.g)(val)
+        }
+    }
+
+    #[inline]
+    fn cancel(_: Self::Receiver) {}
+}
+
+impl<F, T> IntoCallback for SendCallback<F, T>
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    type Callback = Self;
+
+    #[inline]
+    fn into_callback(self) -> (Self::Callback, <Self::Callback as Callback>::Receiver) {
+        (self, ())
+    }
+}
+
+impl<F, T> Callback for SendCallback<F, T>
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    type Receiver = ();
+
+    #[inline]
+    fn call(self) {
+        let _ = self.send.send((self.f)());
+    }
+
+    #[inline]
+    fn callback(_: Self::Receiver) {}
+
+    #[inline]
+    fn cancel(_: Self::Receiver) {}
+}
+
+impl<F, G> IntoCallbackWithCtx for WithCallback<F, G>
+where
+    F: FnOnce(Context) + Send,
+    G: 'static + FnOnce(),
+{
+    type Callback = FnCallbackWithCtx<F>;
+
+    #[inline]
+    fn into_callback_with_ctx(
+        self,
+    ) -> (
+        Self::Callback,
+        <Self::Callback as CallbackWithCtx>::Receiver,
+    ) {
+        (FnCallbackWithCtx { f: self.f }, Box::new(self.g))
+    }
+}
+
+impl<F> CallbackWithCtx for FnCallbackWithCtx<F>
+where
+    F: FnOnce(Context) + Send,
+{
+    type Receiver = Box<dyn FnOnce()>;
+
+    #[inline]
+    fn call(self, ctx: Context) {
+        (self.f)(ctx)
+    }
+
+    #[inline]
+    fn callback(this: Self::Receiver) {
+        this()
+    }
+
+    #[inline]
+    fn cancel(_: Self::Receiver) {}
+}
+
+/// Handle to work proxied with a context, which is considered done once the context is finished.
+///
+/// The context may be stored or sent to other threads, so that the work completes later on, once it's
+/// [`finish`](Context::finish)ed or dropped.
+pub struct Context {
     inner: *mut sys::em_proxying_ctx,
-    _phtm: PhantomData<&'a ()>,
 }
 
-impl<'a> Context<'a> {
+impl Context {
     #[inline]
     pub fn finish(self) {}
 }
 
-impl Drop for Context<'_> {
+impl Drop for Context {
     #[inline]
     fn drop(&mut self) {
         unsafe { sys::emscripten_proxy_finish(self.inner) }
     }
 }
+
+impl Debug for Context {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context").finish_non_exhaustive()
+    }
+}
+
+unsafe impl Send for Context {}
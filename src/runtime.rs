@@ -0,0 +1,566 @@
+use crate::{
+    future::{joinable, JoinHandle},
+    proxying::Queue,
+    sync::spin::SpinLock,
+    sys::{self, PthreadWrapper},
+    thread::create_detached,
+};
+use alloc::collections::VecDeque;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_void, CString},
+    future::Future,
+    io,
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll, Waker},
+    thread::Thread,
+};
+
+const IDLE: u8 = 0;
+const SCHEDULED: u8 = 1;
+const RUNNING: u8 = 2;
+const NOTIFIED: u8 = 3;
+const COMPLETE: u8 = 4;
+
+/// Tasks a worker runs before yielding back to its event loop, so that timers and proxied work get a chance to run.
+const WORKER_BUDGET: usize = 128;
+
+thread_local! {
+    static CURRENT_WORKER: RefCell<Option<(Arc<Shared>, usize)>> = RefCell::new(None);
+}
+
+/// Builds a multi-threaded [`Runtime`] with custom settings.
+pub struct Builder {
+    worker_threads: Option<usize>,
+    max_blocking_threads: usize,
+    thread_name: String,
+    thread_stack_size: Option<usize>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        return Self {
+            worker_threads: None,
+            max_blocking_threads: 8,
+            thread_name: String::from("em-bindgen-worker"),
+            thread_stack_size: None,
+        };
+    }
+
+    /// Sets the number of worker threads that run futures. Defaults to [`available_parallelism`](std::thread::available_parallelism).
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    pub fn worker_threads(&mut self, n: usize) -> &mut Self {
+        assert!(n > 0, "Worker threads cannot be set to 0");
+        self.worker_threads = Some(n);
+        self
+    }
+
+    /// Sets the maximum number of threads spawned to run [`spawn_blocking`](Runtime::spawn_blocking) closures. Defaults to 8.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    pub fn max_blocking_threads(&mut self, n: usize) -> &mut Self {
+        assert!(n > 0, "Max blocking threads cannot be set to 0");
+        self.max_blocking_threads = n;
+        self
+    }
+
+    pub fn thread_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.thread_name = name.into();
+        self
+    }
+
+    pub fn thread_stack_size(&mut self, size: usize) -> &mut Self {
+        self.thread_stack_size = Some(size);
+        self
+    }
+
+    /// Spawns the worker threads and returns the runtime.
+    ///
+    /// Note that Emscripten can only start a new pthread once the calling thread yields back to the
+    /// JavaScript event loop, unless it's already available in the pthread pool (see `PTHREAD_POOL_SIZE`).
+    pub fn build(&self) -> io::Result<Runtime> {
+        let worker_threads = match self.worker_threads {
+            Some(n) => n,
+            None => std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        };
+
+        let shared = Arc::new(Shared {
+            injector: SpinLock::new(VecDeque::new()),
+            workers: (0..worker_threads)
+                .map(|_| SpinLock::new(VecDeque::new()))
+                .collect(),
+            idle: SpinLock::new(Vec::with_capacity(worker_threads)),
+            tasks: SpinLock::new(HashMap::new()),
+            shutdown: AtomicBool::new(false),
+            blocking: Blocking {
+                queue: SpinLock::new(VecDeque::new()),
+                idle: SpinLock::new(Vec::new()),
+                threads: AtomicUsize::new(0),
+                max_threads: self.max_blocking_threads,
+            },
+            thread_name: self.thread_name.clone(),
+            thread_stack_size: self.thread_stack_size,
+        });
+
+        for idx in 0..worker_threads {
+            let arg = Box::into_raw(Box::new((shared.clone(), idx)));
+            if let Err(e) =
+                unsafe { create_detached(self.thread_stack_size, None, start_worker, arg.cast()) }
+            {
+                drop(unsafe { Box::from_raw(arg) });
+                shared.shutdown();
+                return Err(e);
+            }
+        }
+
+        return Ok(Runtime {
+            handle: Handle { shared },
+        });
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A multi-threaded executor, which runs [`Send`] futures on a pool of worker pthreads.
+///
+/// Idle workers steal tasks from busy ones, and return to their JavaScript event loop when there's no
+/// work left, so they never spin. Since workers regularly yield to their event loop, tasks can use the
+/// crate's timers and proxying just like on any other thread. The [`JoinHandle`]s it returns can be awaited from any thread, including the
+/// browser's main thread.
+///
+/// Dropping the runtime shuts it down: workers exit once they finish the task they're running, and
+/// every pending task is dropped.
+pub struct Runtime {
+    handle: Handle,
+}
+
+impl Runtime {
+    /// Creates a runtime with the default settings. See [`Builder`] for more details.
+    #[inline]
+    pub fn new() -> io::Result<Self> {
+        Builder::new().build()
+    }
+
+    #[inline]
+    pub fn spawn<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Future + Send,
+        Fut::Output: 'static + Send,
+    {
+        self.handle.spawn(fut)
+    }
+
+    #[inline]
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: 'static + FnOnce() -> T + Send,
+        T: 'static + Send,
+    {
+        self.handle.spawn_blocking(f)
+    }
+
+    #[inline]
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl Drop for Runtime {
+    #[inline]
+    fn drop(&mut self) {
+        self.handle.shared.shutdown();
+    }
+}
+
+/// A cloneable handle to a [`Runtime`], which can be sent to other threads.
+///
+/// Spawning through a handle after its runtime has been dropped returns a [`JoinHandle`] that
+/// resolves to [`JoinError::Aborted`](crate::future::JoinError::Aborted).
+#[derive(Clone)]
+pub struct Handle {
+    shared: Arc<Shared>,
+}
+
+impl Handle {
+    /// Returns a handle to the runtime whose worker is running the current thread, if any.
+    pub fn try_current() -> Option<Self> {
+        CURRENT_WORKER.with_borrow(|worker| {
+            worker.as_ref().map(|(shared, _)| Handle {
+                shared: shared.clone(),
+            })
+        })
+    }
+
+    /// Spawns a future onto the runtime's workers.
+    pub fn spawn<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Future + Send,
+        Fut::Output: 'static + Send,
+    {
+        let (task, handle) = joinable(fut);
+        let task = Arc::new(Task {
+            future: SpinLock::new(Some(Box::pin(task))),
+            state: AtomicU8::new(SCHEDULED),
+            shared: self.shared.clone(),
+        });
+        self.shared
+            .tasks
+            .lock()
+            .insert(Arc::as_ptr(&task) as usize, Arc::downgrade(&task));
+        self.shared.schedule(task);
+        return handle;
+    }
+
+    /// Runs `f` on a dedicated thread, where it's free to block without stalling the runtime's workers.
+    ///
+    /// Blocking threads never return to their event loop while waiting for jobs, so `f` can't rely on
+    /// JavaScript timers or proxied work running on its own thread.
+    pub fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: 'static + FnOnce() -> T + Send,
+        T: 'static + Send,
+    {
+        let (task, handle) = joinable(async move { f() });
+        self.shared
+            .spawn_blocking(Box::new(move || futures::executor::block_on(task)));
+        return handle;
+    }
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type BlockingJob = Box<dyn FnOnce() + Send>;
+
+struct Shared {
+    injector: SpinLock<VecDeque<Arc<Task>>>,
+    workers: Box<[SpinLock<VecDeque<Arc<Task>>>]>,
+    idle: SpinLock<Vec<(PthreadWrapper, usize)>>,
+    /// Every task that hasn't been dropped yet, including the ones only kept alive by their wakers
+    tasks: SpinLock<HashMap<usize, Weak<Task>>>,
+    shutdown: AtomicBool,
+    blocking: Blocking,
+    thread_name: String,
+    thread_stack_size: Option<usize>,
+}
+
+impl Shared {
+    fn thread_builder(&self, name: String) -> std::thread::Builder {
+        let builder = std::thread::Builder::new().name(name);
+        return match self.thread_stack_size {
+            Some(size) => builder.stack_size(size),
+            None => builder,
+        };
+    }
+
+    fn schedule(self: &Arc<Self>, task: Arc<Task>) {
+        if self.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+
+        let local = CURRENT_WORKER.with_borrow(|worker| match worker {
+            Some((shared, idx)) if Arc::ptr_eq(shared, self) => Some(*idx),
+            _ => None,
+        });
+
+        match local {
+            Some(idx) => self.workers[idx].lock().push_back(task),
+            None => self.injector.lock().push_back(task),
+        }
+
+        if let Some((thread, idx)) = self.idle.lock().pop() {
+            self.resume_worker(thread, idx);
+        }
+    }
+
+    /// Resumes an idle worker from its event loop.
+    fn resume_worker(self: &Arc<Self>, thread: PthreadWrapper, idx: usize) {
+        let shared = self.clone();
+        if !Queue::system().proxy(&thread, move || run_worker(shared, idx)) {
+            log::error!("Error resuming worker {idx}");
+        }
+    }
+
+    fn next_task(&self, idx: usize) -> Option<Arc<Task>> {
+        if let Some(task) = self.workers[idx].lock().pop_front() {
+            return Some(task);
+        }
+
+        if let Some(task) = self.injector.lock().pop_front() {
+            return Some(task);
+        }
+
+        // Steal half of the tasks from the first busy worker we find
+        let len = self.workers.len();
+        for other in (1..len).map(|offset| (idx + offset) % len) {
+            let mut stolen = {
+                let mut queue = self.workers[other].lock();
+                let half = queue.len() - queue.len() / 2;
+                queue.split_off(queue.len() - half)
+            };
+
+            if let Some(task) = stolen.pop_front() {
+                self.workers[idx].lock().append(&mut stolen);
+                return Some(task);
+            }
+        }
+
+        return None;
+    }
+
+    fn has_tasks(&self) -> bool {
+        !self.injector.lock().is_empty()
+            || self.workers.iter().any(|queue| !queue.lock().is_empty())
+    }
+
+    fn spawn_blocking(self: &Arc<Self>, job: BlockingJob) {
+        if self.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+
+        let blocking = &self.blocking;
+        blocking.queue.lock().push_back(job);
+
+        if let Some(thread) = blocking.idle.lock().pop() {
+            return thread.unpark();
+        }
+
+        let mut threads = blocking.threads.load(Ordering::Relaxed);
+        while threads < blocking.max_threads {
+            match blocking.threads.compare_exchange_weak(
+                threads,
+                threads + 1,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    let shared = self.clone();
+                    if let Err(e) = self
+                        .thread_builder(format!("{}-blocking-{threads}", self.thread_name))
+                        .spawn(move || run_blocking(shared))
+                    {
+                        blocking.threads.fetch_sub(1, Ordering::AcqRel);
+                        log::error!("Error spawning blocking thread: {e}");
+                    }
+                    return;
+                }
+                Err(e) => threads = e,
+            }
+        }
+
+        // Every blocking thread is busy, so the job will be picked up by the first one to finish.
+    }
+
+    fn shutdown(self: &Arc<Self>) {
+        if self.shutdown.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        // Idle workers exit once resumed, since they'll find the runtime shut down
+        let idle = core::mem::take(&mut *self.idle.lock());
+        for (thread, idx) in idle {
+            self.resume_worker(thread, idx);
+        }
+        for thread in self.blocking.idle.lock().drain(..) {
+            thread.unpark();
+        }
+
+        // Break the reference cycles between the scheduler and its tasks
+        drop(core::mem::take(&mut *self.injector.lock()));
+        self.blocking.queue.lock().clear();
+
+        // Drop the futures of every remaining task, including the ones waiting on a waker that may never fire
+        let tasks = self
+            .tasks
+            .lock()
+            .drain()
+            .filter_map(|(_, task)| task.upgrade())
+            .collect::<Vec<_>>();
+        for task in tasks {
+            let future = task.future.lock().take();
+            drop(future);
+        }
+    }
+}
+
+struct Blocking {
+    queue: SpinLock<VecDeque<BlockingJob>>,
+    idle: SpinLock<Vec<Thread>>,
+    threads: AtomicUsize,
+    max_threads: usize,
+}
+
+extern "C" fn start_worker(arg: *mut c_void) -> *mut c_void {
+    let (shared, idx) = *unsafe { Box::from_raw(arg.cast::<(Arc<Shared>, usize)>()) };
+    if let Ok(name) = CString::new(format!("{}-{idx}", shared.thread_name)) {
+        unsafe { sys::emscripten_set_thread_name(libc::pthread_self(), name.as_ptr()) };
+    }
+
+    CURRENT_WORKER.set(Some((shared.clone(), idx)));
+    run_worker(shared, idx);
+
+    // Returns to the event loop without exiting the thread, so that timers and proxied work keep running on it.
+    // From now on, the worker is resumed from there.
+    unsafe { sys::emscripten_exit_with_live_runtime() }
+}
+
+/// Runs tasks until there are none left, or the worker's budget runs out, and then returns to the event loop.
+fn run_worker(shared: Arc<Shared>, idx: usize) {
+    let mut budget = WORKER_BUDGET;
+    while !shared.shutdown.load(Ordering::Acquire) {
+        if budget == 0 {
+            // Lets the event loop run before picking up the remaining tasks
+            let this = PthreadWrapper::current();
+            return shared.resume_worker(this, idx);
+        }
+
+        if let Some(task) = shared.next_task(idx) {
+            task.run();
+            budget -= 1;
+            continue;
+        }
+
+        // Register ourselves as idle before checking again, so that we can't miss a wakeup.
+        let this = PthreadWrapper::current();
+        shared.idle.lock().push((this, idx));
+        if shared.has_tasks() || shared.shutdown.load(Ordering::Acquire) {
+            let mut idle = shared.idle.lock();
+            match idle.iter().position(|(thread, _)| thread.0 == this.0) {
+                Some(pos) => drop(idle.swap_remove(pos)),
+                // Someone else already popped us, and is resuming us from the event loop
+                None => return,
+            }
+            continue;
+        }
+
+        return;
+    }
+
+    drop(core::mem::take(&mut *shared.workers[idx].lock()));
+    CURRENT_WORKER.set(None);
+    drop(shared);
+
+    // `pthread_exit` must not unwind through Rust frames, so the worker exits from a fresh event loop callback
+    unsafe { sys::emscripten_set_immediate(Some(exit_worker), core::ptr::null_mut()) };
+}
+
+unsafe extern "C" fn exit_worker(_: *mut c_void) {
+    libc::pthread_exit(core::ptr::null_mut())
+}
+
+fn run_blocking(shared: Arc<Shared>) {
+    let blocking = &shared.blocking;
+
+    while !shared.shutdown.load(Ordering::Acquire) {
+        if let Some(job) = blocking.queue.lock().pop_front() {
+            job();
+            continue;
+        }
+
+        blocking.idle.lock().push(std::thread::current());
+        if !blocking.queue.lock().is_empty() || shared.shutdown.load(Ordering::Acquire) {
+            let current = std::thread::current().id();
+            blocking.idle.lock().retain(|thread| thread.id() != current);
+            continue;
+        }
+
+        std::thread::park();
+    }
+
+    blocking.threads.fetch_sub(1, Ordering::AcqRel);
+}
+
+struct Task {
+    future: SpinLock<Option<BoxFuture>>,
+    state: AtomicU8,
+    shared: Arc<Shared>,
+}
+
+impl Task {
+    fn run(self: Arc<Self>) {
+        if self
+            .state
+            .compare_exchange(SCHEDULED, RUNNING, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Only the worker that moved the task into `RUNNING` can get here, so the lock is uncontended.
+        let mut future = self.future.lock();
+        let Some(fut) = future.as_mut() else {
+            self.state.store(COMPLETE, Ordering::Release);
+            return;
+        };
+
+        if let Poll::Ready(()) = fut.as_mut().poll(&mut cx) {
+            *future = None;
+            self.state.store(COMPLETE, Ordering::Release);
+            return;
+        }
+        drop(future);
+
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // We were woken up while running
+            self.state.store(SCHEDULED, Ordering::Release);
+            self.shared.schedule(self.clone());
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.shared
+            .tasks
+            .lock()
+            .remove(&(self as *const Self as usize));
+    }
+}
+
+impl std::task::Wake for Task {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let new_state = match state {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return,
+            };
+
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(IDLE) => return self.shared.schedule(self.clone()),
+                Ok(_) => return,
+                Err(e) => state = e,
+            }
+        }
+    }
+}
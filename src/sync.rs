@@ -1 +1,13 @@
+pub mod barrier;
 pub mod mutex;
+pub mod notify;
+pub mod rwlock;
+pub mod semaphore;
+pub(crate) mod spin;
+pub(crate) mod waiters;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use mutex::{Mutex, MutexGuard};
+pub use notify::Notify;
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use semaphore::{OwnedSemaphorePermit, Semaphore, SemaphorePermit};
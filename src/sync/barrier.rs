@@ -0,0 +1,108 @@
+use super::{
+    spin::SpinLock,
+    waiters::{WaitEntry, Waiters},
+};
+use core::future::poll_fn;
+
+/// Enables multiple tasks to synchronize the beginning of some computation, parking them instead of blocking their threads.
+pub struct Barrier {
+    n: usize,
+    state: SpinLock<State>,
+    waiters: Waiters,
+}
+
+/// Number of tasks that arrived at the current rendezvous, and how many rendezvous have completed.
+///
+/// They're updated together, so that no task is counted in one generation but released by another.
+struct State {
+    count: usize,
+    generation: usize,
+}
+
+impl Barrier {
+    /// Creates a new barrier that releases its waiting tasks once `n` of them have called [`wait`](Barrier::wait).
+    pub const fn new(n: usize) -> Self {
+        return Self {
+            n,
+            state: SpinLock::new(State {
+                count: 0,
+                generation: 0,
+            }),
+            waiters: Waiters::new(),
+        };
+    }
+
+    /// Parks the current task until all `n` tasks have rendezvoused here.
+    ///
+    /// The barrier is reusable: once released, the next `n` calls form a new rendezvous. Dropping the
+    /// returned future after it has been polled still counts as having arrived at the barrier.
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let generation = {
+            let mut state = self.state.lock();
+            state.count += 1;
+            if state.count >= self.n {
+                state.count = 0;
+                state.generation = state.generation.wrapping_add(1);
+                drop(state);
+
+                self.waiters.notify_all();
+                return BarrierWaitResult { is_leader: true };
+            }
+            state.generation
+        };
+
+        let mut entry = WaitEntry::new(&self.waiters);
+        poll_fn(|cx| {
+            entry.poll_acquire(cx, || {
+                (self.state.lock().generation != generation).then_some(())
+            })
+        })
+        .await;
+
+        return BarrierWaitResult { is_leader: false };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+}
+
+impl BarrierWaitResult {
+    /// Returns `true` for exactly one task of every rendezvous.
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Barrier;
+    use futures::{task::noop_waker_ref, FutureExt};
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn barrier_is_reusable_across_generations() {
+        let barrier = Barrier::new(2);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        for _ in 0..2 {
+            let mut a = Box::pin(barrier.wait());
+            assert!(a.poll_unpin(&mut cx).is_pending());
+
+            let Poll::Ready(b) = Box::pin(barrier.wait()).poll_unpin(&mut cx) else {
+                panic!("the last task didn't release the barrier");
+            };
+            assert!(b.is_leader());
+
+            let Poll::Ready(a) = a.poll_unpin(&mut cx) else {
+                panic!("the waiting task wasn't released");
+            };
+            assert!(!a.is_leader());
+        }
+
+        // A rendezvous that already completed doesn't release the next one
+        assert!(Box::pin(barrier.wait()).poll_unpin(&mut cx).is_pending());
+    }
+}
@@ -1,11 +1,12 @@
-use crate::future::block_on;
-use alloc::collections::VecDeque;
+use super::waiters::{WaitEntry, Waiters};
 use core::{
     cell::UnsafeCell,
+    future::poll_fn,
+    marker::PhantomData,
     ops::{Deref, DerefMut},
     sync::atomic::{AtomicBool, Ordering},
 };
-use utils_atomics::flag::mpsc::{async_flag, AsyncFlag};
+use docfg::docfg;
 
 /// A Mutex implementation designed to block the thread as little as possible, instead yielding back to the JavaScript runtime whenever possible.
 pub struct Mutex<T: ?Sized> {
@@ -26,18 +27,58 @@ impl<T: ?Sized> Mutex<T> {
 
     #[inline]
     pub fn try_lock(&self) -> Option<MutexGuard<T>> {
-        self.raw.try_lock().then(|| MutexGuard { parent: self })
+        self.raw.try_lock().then(|| MutexGuard::new(self))
     }
 
+    /// Locks the mutex, parking the current task until it becomes available.
     #[inline]
-    pub fn lock(&self) -> MutexGuard<T> {
-        self.raw.lock();
-        return MutexGuard { parent: self };
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.raw.lock().await;
+        return MutexGuard::new(self);
+    }
+
+    /// Locks the mutex, blocking the current thread (while yielding to the JavaScript event loop) until it becomes available.
+    #[docfg(feature = "asyncify")]
+    #[inline]
+    pub fn blocking_lock(&self) -> MutexGuard<'_, T> {
+        crate::future::block_on(self.raw.lock());
+        return MutexGuard::new(self);
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T
+    where
+        T: Sized,
+    {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
     }
 }
 
 pub struct MutexGuard<'a, T: ?Sized> {
     parent: &'a Mutex<T>,
+    _phtm: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    #[inline]
+    fn new(parent: &'a Mutex<T>) -> Self {
+        return Self {
+            parent,
+            _phtm: PhantomData,
+        };
+    }
 }
 
 impl<T: ?Sized> Deref for MutexGuard<'_, T> {
@@ -84,65 +125,17 @@ impl RawMutex {
     #[inline]
     pub fn try_lock(&self) -> bool {
         self.locked
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
     }
 
-    pub fn lock(&self) {
-        while !self.try_lock() {
-            let (send, recv) = async_flag();
-            self.waiters.push(send);
-            block_on(recv);
-        }
+    pub async fn lock(&self) {
+        let mut entry = WaitEntry::new(&self.waiters);
+        poll_fn(|cx| entry.poll_acquire(cx, || self.try_lock().then_some(()))).await
     }
 
     pub unsafe fn unlock(&self) {
-        self.locked.store(false, Ordering::AcqRel);
-        if let Some(waiter) = self.waiters.pop() {
-            waiter.mark();
-        }
+        self.locked.store(false, Ordering::Release);
+        self.waiters.notify_one();
     }
 }
-
-struct Waiters {
-    busy: AtomicBool,
-    waiters: UnsafeCell<VecDeque<AsyncFlag>>,
-}
-
-impl Waiters {
-    const fn new() -> Self {
-        Self {
-            busy: AtomicBool::new(false),
-            waiters: UnsafeCell::new(VecDeque::new()),
-        }
-    }
-
-    #[inline]
-    fn push(&self, evt: AsyncFlag) {
-        self.wait();
-        unsafe { &mut *self.waiters.get() }.push_back(evt);
-        self.busy.store(false, Ordering::Release);
-    }
-
-    #[inline]
-    fn pop(&self) -> Option<AsyncFlag> {
-        self.wait();
-        let res = unsafe { &mut *self.waiters.get() }.pop_front();
-        self.busy.store(false, Ordering::Release);
-        return res;
-    }
-
-    #[inline]
-    fn wait(&self) {
-        while self
-            .busy
-            .compare_exchange_weak(false, true, Ordering::AcqRel, Ordering::Relaxed)
-            .is_err()
-        {
-            std::hint::spin_loop();
-        }
-    }
-}
-
-unsafe impl Send for Waiters {}
-unsafe impl Sync for Waiters {}
@@ -0,0 +1,96 @@
+use super::waiters::{WaitEntry, Waiters};
+use core::{
+    future::{poll_fn, Future},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Notifies one or more parked tasks of an event, without carrying any data.
+///
+/// Like [`Thread::unpark`](std::thread::Thread::unpark), a call to [`notify_one`](Notify::notify_one)
+/// with no task waiting stores a permit, which the next call to [`notified`](Notify::notified)
+/// consumes immediately.
+pub struct Notify {
+    permit: AtomicBool,
+    generation: AtomicUsize,
+    waiters: Waiters,
+}
+
+impl Notify {
+    pub const fn new() -> Self {
+        return Self {
+            permit: AtomicBool::new(false),
+            generation: AtomicUsize::new(0),
+            waiters: Waiters::new(),
+        };
+    }
+
+    /// Waits for a notification.
+    ///
+    /// The call to [`notify_waiters`](Notify::notify_waiters) is observed as soon as this method is called,
+    /// even if the returned future hasn't been polled yet.
+    pub fn notified(&self) -> impl '_ + Future<Output = ()> {
+        let generation = self.generation.load(Ordering::Acquire);
+        return async move {
+            let mut entry = WaitEntry::new(&self.waiters);
+            poll_fn(|cx| {
+                entry.poll_acquire(cx, || {
+                    let notified = self.generation.load(Ordering::Acquire) != generation
+                        || self.permit.swap(false, Ordering::AcqRel);
+                    notified.then_some(())
+                })
+            })
+            .await
+        };
+    }
+
+    /// Wakes the oldest waiting task, or stores a permit for the next one if there are none.
+    pub fn notify_one(&self) {
+        self.permit.store(true, Ordering::Release);
+        self.waiters.notify_one();
+    }
+
+    /// Wakes every task currently waiting, without storing a permit.
+    pub fn notify_waiters(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.waiters.notify_all();
+    }
+}
+
+impl Default for Notify {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::waiters::tests::WakeFlag, Notify};
+    use futures::{task::noop_waker_ref, FutureExt};
+    use std::task::Context;
+
+    #[test]
+    fn notify_one_stores_a_permit_without_waiters() {
+        let notify = Notify::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        notify.notify_one();
+        assert!(Box::pin(notify.notified()).poll_unpin(&mut cx).is_ready());
+        // The permit is consumed by the first waiter
+        assert!(Box::pin(notify.notified()).poll_unpin(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn notify_one_wakes_a_parked_waiter() {
+        let notify = Notify::new();
+        let (woken, waker) = WakeFlag::new();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut notified = Box::pin(notify.notified());
+        assert!(notified.poll_unpin(&mut cx).is_pending());
+
+        notify.notify_one();
+        assert!(woken.take(), "the wakeup was lost");
+        assert!(notified.poll_unpin(&mut cx).is_ready());
+    }
+}
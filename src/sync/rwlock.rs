@@ -0,0 +1,261 @@
+use super::waiters::{WaitEntry, Waiters};
+use core::{
+    cell::UnsafeCell,
+    future::poll_fn,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use docfg::docfg;
+
+const WRITER: usize = 1 << (usize::BITS - 1);
+
+/// A reader-writer lock that parks tasks instead of blocking the thread, yielding back to the JavaScript runtime whenever possible.
+pub struct RwLock<T: ?Sized> {
+    raw: RawRwLock,
+    inner: UnsafeCell<T>,
+}
+
+impl<T: ?Sized> RwLock<T> {
+    pub const fn new(val: T) -> Self
+    where
+        T: Sized,
+    {
+        Self {
+            raw: RawRwLock::new(),
+            inner: UnsafeCell::new(val),
+        }
+    }
+
+    #[inline]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<T>> {
+        self.raw.try_read().then(|| RwLockReadGuard::new(self))
+    }
+
+    #[inline]
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<T>> {
+        self.raw.try_write().then(|| RwLockWriteGuard::new(self))
+    }
+
+    /// Locks this lock with shared read access, parking the current task until no writer holds it.
+    #[inline]
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.raw.read().await;
+        return RwLockReadGuard::new(self);
+    }
+
+    /// Locks this lock with exclusive write access, parking the current task until no other reader or writer holds it.
+    #[inline]
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.raw.write().await;
+        return RwLockWriteGuard::new(self);
+    }
+
+    #[docfg(feature = "asyncify")]
+    #[inline]
+    pub fn blocking_read(&self) -> RwLockReadGuard<'_, T> {
+        crate::future::block_on(self.raw.read());
+        return RwLockReadGuard::new(self);
+    }
+
+    #[docfg(feature = "asyncify")]
+    #[inline]
+    pub fn blocking_write(&self) -> RwLockWriteGuard<'_, T> {
+        crate::future::block_on(self.raw.write());
+        return RwLockWriteGuard::new(self);
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T
+    where
+        T: Sized,
+    {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    parent: &'a RwLock<T>,
+    _phtm: PhantomData<&'a T>,
+}
+
+impl<'a, T: ?Sized> RwLockReadGuard<'a, T> {
+    #[inline]
+    fn new(parent: &'a RwLock<T>) -> Self {
+        return Self {
+            parent,
+            _phtm: PhantomData,
+        };
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.parent.inner.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.parent.raw.unlock_read() }
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    parent: &'a RwLock<T>,
+    _phtm: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
+    #[inline]
+    fn new(parent: &'a RwLock<T>) -> Self {
+        return Self {
+            parent,
+            _phtm: PhantomData,
+        };
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.parent.inner.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.parent.inner.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.parent.raw.unlock_write() }
+    }
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+struct RawRwLock {
+    state: AtomicUsize,
+    readers: Waiters,
+    writers: Waiters,
+}
+
+impl RawRwLock {
+    const fn new() -> Self {
+        return Self {
+            state: AtomicUsize::new(0),
+            readers: Waiters::new(),
+            writers: Waiters::new(),
+        };
+    }
+
+    fn try_read(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & WRITER != 0 {
+                return false;
+            }
+
+            let readers = state.checked_add(1).expect("too many readers");
+            match self.state.compare_exchange_weak(
+                state,
+                readers,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(e) => state = e,
+            }
+        }
+    }
+
+    #[inline]
+    fn try_write(&self) -> bool {
+        self.state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    async fn read(&self) {
+        let mut entry = WaitEntry::new(&self.readers);
+        poll_fn(|cx| entry.poll_acquire(cx, || self.try_read().then_some(()))).await
+    }
+
+    async fn write(&self) {
+        let mut entry = WaitEntry::new(&self.writers);
+        poll_fn(|cx| entry.poll_acquire(cx, || self.try_write().then_some(()))).await
+    }
+
+    unsafe fn unlock_read(&self) {
+        // The last reader hands the lock over to a writer.
+        if self.state.fetch_sub(1, Ordering::Release) == 1 {
+            self.writers.notify_one();
+        }
+    }
+
+    unsafe fn unlock_write(&self) {
+        self.state.store(0, Ordering::Release);
+        // Whoever loses the race for the lock parks again.
+        self.writers.notify_one();
+        self.readers.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::waiters::tests::WakeFlag, RwLock};
+    use futures::FutureExt;
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn last_reader_hands_the_lock_to_a_parked_writer() {
+        let lock = RwLock::new(0);
+        let reader = lock.try_read().unwrap();
+
+        let (writer_woken, writer_waker) = WakeFlag::new();
+        let mut writer_cx = Context::from_waker(&writer_waker);
+        let mut write = Box::pin(lock.write());
+        assert!(write.poll_unpin(&mut writer_cx).is_pending());
+
+        drop(reader);
+        assert!(writer_woken.take(), "the writer wasn't woken");
+        let Poll::Ready(mut guard) = write.poll_unpin(&mut writer_cx) else {
+            panic!("the writer didn't get the lock");
+        };
+        *guard = 1;
+
+        // Readers arriving meanwhile park until the writer is done
+        let (reader_woken, reader_waker) = WakeFlag::new();
+        let mut reader_cx = Context::from_waker(&reader_waker);
+        let mut read = Box::pin(lock.read());
+        assert!(read.poll_unpin(&mut reader_cx).is_pending());
+
+        drop(guard);
+        assert!(reader_woken.take(), "the reader wasn't woken");
+        assert!(matches!(read.poll_unpin(&mut reader_cx), Poll::Ready(guard) if *guard == 1));
+    }
+}
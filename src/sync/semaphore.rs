@@ -0,0 +1,197 @@
+use super::waiters::{WaitEntry, Waiters};
+use core::{
+    future::poll_fn,
+    mem::ManuallyDrop,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::sync::Arc;
+
+/// A counting semaphore that parks tasks instead of blocking the thread while waiting for permits.
+///
+/// Tasks are woken in the order they started waiting. A task whose request can't be satisfied yet
+/// keeps its place in the queue, and passes the wakeup on to the tasks behind it.
+pub struct Semaphore {
+    permits: AtomicUsize,
+    waiters: Waiters,
+}
+
+impl Semaphore {
+    pub const fn new(permits: usize) -> Self {
+        return Self {
+            permits: AtomicUsize::new(permits),
+            waiters: Waiters::new(),
+        };
+    }
+
+    #[inline]
+    pub fn available_permits(&self) -> usize {
+        self.permits.load(Ordering::Acquire)
+    }
+
+    /// Adds `n` new permits to the semaphore, waking tasks that may now be able to acquire them.
+    pub fn add_permits(&self, n: usize) {
+        if n == 0 {
+            return;
+        }
+
+        self.permits.fetch_add(n, Ordering::Release);
+        self.waiters.notify(n);
+    }
+
+    #[inline]
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.try_acquire_many(1)
+    }
+
+    pub fn try_acquire_many(&self, n: usize) -> Option<SemaphorePermit<'_>> {
+        self.try_take(n).then(|| SemaphorePermit {
+            parent: self,
+            permits: n,
+        })
+    }
+
+    #[inline]
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.acquire_many(1).await
+    }
+
+    pub async fn acquire_many(&self, n: usize) -> SemaphorePermit<'_> {
+        let mut entry = WaitEntry::new(&self.waiters);
+        poll_fn(|cx| entry.poll_acquire(cx, || self.try_take(n).then_some(()))).await;
+        return SemaphorePermit {
+            parent: self,
+            permits: n,
+        };
+    }
+
+    #[inline]
+    pub fn try_acquire_owned(self: Arc<Self>) -> Option<OwnedSemaphorePermit> {
+        self.try_acquire_many_owned(1)
+    }
+
+    pub fn try_acquire_many_owned(self: Arc<Self>, n: usize) -> Option<OwnedSemaphorePermit> {
+        self.try_take(n).then(|| OwnedSemaphorePermit {
+            parent: self,
+            permits: n,
+        })
+    }
+
+    #[inline]
+    pub async fn acquire_owned(self: Arc<Self>) -> OwnedSemaphorePermit {
+        self.acquire_many_owned(1).await
+    }
+
+    pub async fn acquire_many_owned(self: Arc<Self>, n: usize) -> OwnedSemaphorePermit {
+        let permits = self.acquire_many(n).await.forget();
+        return OwnedSemaphorePermit {
+            parent: self,
+            permits,
+        };
+    }
+
+    fn try_take(&self, n: usize) -> bool {
+        let mut permits = self.permits.load(Ordering::Relaxed);
+        loop {
+            let Some(remaining) = permits.checked_sub(n) else {
+                return false;
+            };
+
+            match self.permits.compare_exchange_weak(
+                permits,
+                remaining,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(e) => permits = e,
+            }
+        }
+    }
+}
+
+/// A set of permits borrowed from a [`Semaphore`], returned to it when dropped.
+pub struct SemaphorePermit<'a> {
+    parent: &'a Semaphore,
+    permits: usize,
+}
+
+impl SemaphorePermit<'_> {
+    #[inline]
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Forgets the permits without releasing them back to the semaphore, returning how many there were.
+    #[inline]
+    pub fn forget(self) -> usize {
+        ManuallyDrop::new(self).permits
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.parent.add_permits(self.permits)
+    }
+}
+
+/// A set of permits owned from an [`Arc`]-shared [`Semaphore`], returned to it when dropped.
+pub struct OwnedSemaphorePermit {
+    parent: Arc<Semaphore>,
+    permits: usize,
+}
+
+impl OwnedSemaphorePermit {
+    #[inline]
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    #[inline]
+    pub fn semaphore(&self) -> &Arc<Semaphore> {
+        &self.parent
+    }
+
+    /// Forgets the permits without releasing them back to the semaphore, returning how many there were.
+    pub fn forget(self) -> usize {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { core::ptr::drop_in_place(&mut this.parent) };
+        return this.permits;
+    }
+}
+
+impl Drop for OwnedSemaphorePermit {
+    #[inline]
+    fn drop(&mut self) {
+        self.parent.add_permits(self.permits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Semaphore;
+    use futures::{task::noop_waker_ref, FutureExt};
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn wakeup_is_forwarded_past_unsatisfied_waiter() {
+        let sem = Semaphore::new(0);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut a = Box::pin(sem.acquire_many(5));
+        let mut b = Box::pin(sem.acquire_many(1));
+        assert!(a.poll_unpin(&mut cx).is_pending());
+        assert!(b.poll_unpin(&mut cx).is_pending());
+
+        // Only `a` is notified, but it can't take 5 permits, so `b` must get the wakeup instead
+        sem.add_permits(1);
+        assert!(a.poll_unpin(&mut cx).is_pending());
+        let Poll::Ready(permit) = b.poll_unpin(&mut cx) else {
+            panic!("the wakeup was lost");
+        };
+        assert_eq!(permit.forget(), 1);
+
+        sem.add_permits(5);
+        assert!(matches!(a.poll_unpin(&mut cx), Poll::Ready(_)));
+    }
+}
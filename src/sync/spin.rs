@@ -0,0 +1,67 @@
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A lock for very short critical sections, which never parks the thread.
+///
+/// Unlike [`Mutex`](super::Mutex), it can be used from synchronous code on the browser's main thread.
+pub(crate) struct SpinLock<T: ?Sized> {
+    busy: AtomicBool,
+    inner: UnsafeCell<T>,
+}
+
+impl<T: ?Sized> SpinLock<T> {
+    pub const fn new(val: T) -> Self
+    where
+        T: Sized,
+    {
+        Self {
+            busy: AtomicBool::new(false),
+            inner: UnsafeCell::new(val),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        while self
+            .busy
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            std::hint::spin_loop();
+        }
+        return SpinLockGuard { parent: self };
+    }
+}
+
+pub(crate) struct SpinLockGuard<'a, T: ?Sized> {
+    parent: &'a SpinLock<T>,
+}
+
+impl<T: ?Sized> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.parent.inner.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinLockGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.parent.inner.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SpinLockGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.parent.busy.store(false, Ordering::Release);
+    }
+}
+
+unsafe impl<T: ?Sized + Send> Send for SpinLock<T> {}
+unsafe impl<T: ?Sized + Send> Sync for SpinLock<T> {}
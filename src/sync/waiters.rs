@@ -0,0 +1,243 @@
+use super::spin::SpinLock;
+use alloc::collections::VecDeque;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll},
+};
+use futures::task::AtomicWaker;
+use std::sync::Arc;
+
+/// A single task parked on a [`Waiters`] queue.
+struct Waiter {
+    notified: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Waiter {
+    fn new() -> Self {
+        return Self {
+            notified: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        };
+    }
+}
+
+/// FIFO queue of parked tasks, shared by all the primitives in [`sync`](super).
+///
+/// Waking a task goes through its [`Waker`](std::task::Waker), so tasks parked on other threads are
+/// resumed by their own executor instead of spinning on the lock.
+pub(crate) struct Waiters {
+    waiters: SpinLock<VecDeque<Arc<Waiter>>>,
+}
+
+impl Waiters {
+    pub const fn new() -> Self {
+        Self {
+            waiters: SpinLock::new(VecDeque::new()),
+        }
+    }
+
+    #[inline]
+    fn push(&self, waiter: Arc<Waiter>) {
+        self.waiters.lock().push_back(waiter)
+    }
+
+    /// Removes `waiter` from the queue, returning `false` if it wasn't in it.
+    #[inline]
+    fn remove(&self, waiter: &Arc<Waiter>) -> bool {
+        let mut waiters = self.waiters.lock();
+        match waiters.iter().position(|other| Arc::ptr_eq(other, waiter)) {
+            Some(idx) => {
+                waiters.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wakes the oldest parked task that hasn't been notified yet, returning `false` if there were none.
+    pub fn notify_one(&self) -> bool {
+        return self.notify(1) == 1;
+    }
+
+    /// Wakes up to `n` parked tasks that haven't been notified yet, returning how many were woken.
+    ///
+    /// Woken tasks keep their place in the queue until they acquire the resource, so that a task whose
+    /// request can't be satisfied yet doesn't lose its turn.
+    pub fn notify(&self, n: usize) -> usize {
+        let woken = {
+            let waiters = self.waiters.lock();
+            waiters
+                .iter()
+                .filter(|waiter| !waiter.notified.swap(true, Ordering::AcqRel))
+                .take(n)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        for waiter in woken.iter() {
+            waiter.waker.wake();
+        }
+        return woken.len();
+    }
+
+    /// Wakes every parked task.
+    #[inline]
+    pub fn notify_all(&self) -> usize {
+        self.notify(usize::MAX)
+    }
+
+    /// Passes the notification of `waiter`, which couldn't acquire the resource, on to the next task
+    /// queued behind it that hasn't been notified yet.
+    fn forward(&self, waiter: &Arc<Waiter>) {
+        let next = {
+            let waiters = self.waiters.lock();
+            if !waiter.notified.swap(false, Ordering::AcqRel) {
+                return;
+            }
+
+            waiters
+                .iter()
+                .skip_while(|other| !Arc::ptr_eq(other, waiter))
+                .skip(1)
+                .find(|other| !other.notified.swap(true, Ordering::AcqRel))
+                .cloned()
+        };
+
+        if let Some(next) = next {
+            next.waker.wake();
+        }
+    }
+}
+
+/// The registration of a pending future inside a [`Waiters`] queue.
+///
+/// If the future is dropped after being woken but before acquiring the resource, the wakeup is
+/// passed on to the next task in the queue, so it never gets lost.
+pub(crate) struct WaitEntry<'a> {
+    queue: &'a Waiters,
+    waiter: Option<Arc<Waiter>>,
+}
+
+impl<'a> WaitEntry<'a> {
+    #[inline]
+    pub const fn new(queue: &'a Waiters) -> Self {
+        return Self {
+            queue,
+            waiter: None,
+        };
+    }
+
+    /// Attempts to acquire the resource with `try_acquire`, parking the current task on the queue if
+    /// it isn't available.
+    pub fn poll_acquire<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut try_acquire: impl FnMut() -> Option<T>,
+    ) -> Poll<T> {
+        if let Some(val) = try_acquire() {
+            self.complete();
+            return Poll::Ready(val);
+        }
+
+        match self.waiter {
+            Some(ref waiter) => {
+                waiter.waker.register(cx.waker());
+                self.queue.forward(waiter);
+            }
+            None => {
+                let waiter = Arc::new(Waiter::new());
+                waiter.waker.register(cx.waker());
+                self.queue.push(waiter.clone());
+                self.waiter = Some(waiter);
+            }
+        }
+
+        // The resource may have been released while we were registering ourselves.
+        if let Some(val) = try_acquire() {
+            self.complete();
+            return Poll::Ready(val);
+        }
+
+        return Poll::Pending;
+    }
+
+    #[inline]
+    fn complete(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.queue.remove(&waiter);
+        }
+    }
+}
+
+impl Drop for WaitEntry<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            if self.queue.remove(&waiter) && waiter.notified.load(Ordering::Acquire) {
+                self.queue.notify_one();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::{WaitEntry, Waiters};
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Wake, Waker},
+    };
+
+    /// A waker that records whether it was woken.
+    pub(in crate::sync) struct WakeFlag(AtomicBool);
+
+    impl WakeFlag {
+        pub fn new() -> (Arc<Self>, Waker) {
+            let flag = Arc::new(Self(AtomicBool::new(false)));
+            return (flag.clone(), Waker::from(flag));
+        }
+
+        pub fn take(&self) -> bool {
+            self.0.swap(false, Ordering::AcqRel)
+        }
+    }
+
+    impl Wake for WakeFlag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn cancelled_waiter_forwards_its_wakeup() {
+        let queue = Waiters::new();
+        let available = AtomicBool::new(false);
+        let try_acquire = || available.swap(false, Ordering::AcqRel).then_some(());
+
+        let (a_woken, a_waker) = WakeFlag::new();
+        let (b_woken, b_waker) = WakeFlag::new();
+        let mut a = WaitEntry::new(&queue);
+        let mut b = WaitEntry::new(&queue);
+        assert!(a
+            .poll_acquire(&mut Context::from_waker(&a_waker), try_acquire)
+            .is_pending());
+        assert!(b
+            .poll_acquire(&mut Context::from_waker(&b_waker), try_acquire)
+            .is_pending());
+
+        available.store(true, Ordering::Release);
+        assert!(queue.notify_one());
+        assert!(a_woken.take());
+        assert!(!b_woken.take());
+
+        // `a` is cancelled before it gets to acquire, so `b` must be woken in its place
+        drop(a);
+        assert!(b_woken.take());
+        assert!(b
+            .poll_acquire(&mut Context::from_waker(&b_waker), try_acquire)
+            .is_ready());
+    }
+}
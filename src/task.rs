@@ -0,0 +1,219 @@
+//! Task-local storage and task configuration for the [`spawn_local`](crate::future::spawn_local) runtime.
+
+#[cfg(feature = "proxying")]
+use crate::future::JoinHandle;
+use docfg::docfg;
+use pin_project::pin_project;
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Declares new task-local keys of type [`LocalKey`].
+///
+/// ```ignore
+/// em_bindgen::task_local! {
+///     pub static REQUEST_ID: u32;
+///     static USER: String;
+/// }
+///
+/// REQUEST_ID.scope(1, async {
+///     assert_eq!(REQUEST_ID.get(), 1);
+/// });
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+        $crate::task_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_local_inner {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $t:ty) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task::LocalKey<$t> = {
+            $crate::libstd::thread_local! {
+                static __KEY: $crate::libstd::cell::RefCell<$crate::libstd::option::Option<$t>> =
+                    const { $crate::libstd::cell::RefCell::new($crate::libstd::option::Option::None) };
+            }
+
+            $crate::task::LocalKey { inner: __KEY }
+        };
+    };
+}
+
+pub use crate::task_local;
+pub use join_set::JoinSet;
+pub use scope::{scope, Scope, ScopeFuture, ScopedJoinHandle};
+
+mod join_set;
+mod scope;
+
+task_local! {
+    static NAME: Option<Arc<str>>;
+}
+
+/// A key for task-local data, declared with [`task_local!`].
+///
+/// The value is only set while polling a future wrapped with [`scope`](LocalKey::scope), so it follows the
+/// task across every thread that polls it.
+pub struct LocalKey<T: 'static> {
+    #[doc(hidden)]
+    pub inner: std::thread::LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> LocalKey<T> {
+    /// Sets the value of the key to `value` while polling `fut`.
+    #[inline]
+    pub fn scope<Fut: Future>(&'static self, value: T, fut: Fut) -> TaskLocalFuture<T, Fut> {
+        return TaskLocalFuture {
+            key: self,
+            slot: Some(value),
+            fut,
+        };
+    }
+
+    /// Sets the value of the key to `value` while running `f`.
+    #[inline]
+    pub fn sync_scope<F: FnOnce() -> R, R>(&'static self, value: T, f: F) -> R {
+        let mut slot = Some(value);
+        return self.scope_inner(&mut slot, f);
+    }
+
+    /// # Panics
+    /// Panics if the key isn't set in the current task.
+    #[inline]
+    pub fn with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> R {
+        self.try_with(f)
+            .expect("cannot access a task-local value outside of its scope")
+    }
+
+    pub fn try_with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> Result<R, AccessError> {
+        return self.inner.with(|cell| match cell.borrow().as_ref() {
+            Some(value) => Ok(f(value)),
+            None => Err(AccessError),
+        });
+    }
+
+    /// Returns a copy of the value.
+    ///
+    /// # Panics
+    /// Panics if the key isn't set in the current task.
+    #[inline]
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    fn scope_inner<F: FnOnce() -> R, R>(&'static self, slot: &mut Option<T>, f: F) -> R {
+        struct Guard<'a, T: 'static> {
+            key: &'static LocalKey<T>,
+            slot: &'a mut Option<T>,
+        }
+
+        impl<T: 'static> Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                self.key
+                    .inner
+                    .with(|cell| std::mem::swap(self.slot, &mut *cell.borrow_mut()));
+            }
+        }
+
+        self.inner
+            .with(|cell| std::mem::swap(slot, &mut *cell.borrow_mut()));
+
+        // Swaps the previous value back in, even if `f` panics
+        let _guard = Guard { key: self, slot };
+        return f();
+    }
+}
+
+impl<T: 'static> std::fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalKey").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`LocalKey::scope`].
+#[pin_project]
+pub struct TaskLocalFuture<T: 'static, Fut> {
+    key: &'static LocalKey<T>,
+    slot: Option<T>,
+    #[pin]
+    fut: Fut,
+}
+
+impl<T: 'static, Fut: Future> Future for TaskLocalFuture<T, Fut> {
+    type Output = Fut::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = this.fut;
+        return this.key.scope_inner(this.slot, || fut.poll(cx));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("The task-local value isn't set in the current scope")]
+pub struct AccessError;
+
+/// Returns the name of the current task, if it was given one with [`Builder::name`].
+pub fn name() -> Option<Arc<str>> {
+    NAME.try_with(Clone::clone).ok().flatten()
+}
+
+#[inline]
+pub(crate) fn scope_name<Fut: Future>(
+    name: Option<Arc<str>>,
+    fut: Fut,
+) -> TaskLocalFuture<Option<Arc<str>>, Fut> {
+    NAME.scope(name, fut)
+}
+
+/// Configures a task before spawning it.
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    name: Option<Arc<str>>,
+}
+
+impl Builder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the task, so that it can be identified in logs and in [`JoinError::Panic`](crate::future::JoinError::Panic).
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Spawns `fut` on the local runtime of the current thread, like [`spawn_local`](crate::future::spawn_local).
+    #[docfg(feature = "proxying")]
+    pub fn spawn_local<Fut>(self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Future,
+        Fut::Output: 'static,
+    {
+        if let Some(ref name) = self.name {
+            log::debug!("Spawning task '{name}'");
+        }
+
+        crate::future::spawn_local_named(fut, self.name)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::future::{AbortHandle, JoinError, JoinHandle};
+use docfg::docfg;
+use futures::{stream::FuturesUnordered, Stream};
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A collection of spawned tasks, which can be awaited in the order they complete.
+///
+/// When the set is dropped, all of its tasks are aborted.
+pub struct JoinSet<T> {
+    handles: FuturesUnordered<JoinHandle<T>>,
+}
+
+impl<T> JoinSet<T> {
+    #[inline]
+    pub fn new() -> Self {
+        return Self {
+            handles: FuturesUnordered::new(),
+        };
+    }
+
+    /// Returns the number of tasks in the set, including the ones that have completed but haven't been
+    /// joined yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Spawns `fut` on the local runtime of the current thread, adding it to the set.
+    #[docfg(feature = "proxying")]
+    pub fn spawn_local<Fut>(&mut self, fut: Fut) -> AbortHandle
+    where
+        T: 'static,
+        Fut: 'static + Future<Output = T>,
+    {
+        self.insert(crate::future::spawn_local(fut))
+    }
+
+    /// Adds an already spawned task to the set, like the ones returned by [`spawn_on`](crate::future::spawn_on)
+    /// or [`Builder::spawn_local`](super::Builder::spawn_local).
+    pub fn insert(&mut self, handle: JoinHandle<T>) -> AbortHandle {
+        let abort = handle.abort_handle();
+        self.handles.push(handle);
+        return abort;
+    }
+
+    /// Waits for the next task to complete, returning its output.
+    ///
+    /// Returns `None` if the set is empty.
+    #[inline]
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        std::future::poll_fn(|cx| self.poll_join_next(cx)).await
+    }
+
+    #[inline]
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JoinError>>> {
+        Pin::new(&mut self.handles).poll_next(cx)
+    }
+
+    /// Waits for every task to complete, returning their outputs in the order they completed.
+    pub async fn join_all(mut self) -> Vec<Result<T, JoinError>> {
+        let mut results = Vec::with_capacity(self.len());
+        while let Some(res) = self.join_next().await {
+            results.push(res);
+        }
+        return results;
+    }
+
+    /// Aborts every task in the set, which is kept until they are joined.
+    pub fn abort_all(&self) {
+        self.handles.iter().for_each(JoinHandle::abort);
+    }
+
+    /// Aborts every task in the set, and waits for them to stop.
+    pub async fn shutdown(&mut self) {
+        self.abort_all();
+        while self.join_next().await.is_some() {}
+    }
+}
+
+impl<T> Default for JoinSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for JoinSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JoinSet")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for JoinSet<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.abort_all();
+    }
+}
@@ -0,0 +1,175 @@
+use futures::{
+    channel::oneshot,
+    future::LocalBoxFuture,
+    stream::{FuturesUnordered, StreamExt},
+    FutureExt,
+};
+use std::{
+    cell::RefCell,
+    fmt::Debug,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Runs the future returned by `f`, alongside every task it spawns on the given [`Scope`].
+///
+/// Unlike spawned tasks, scoped tasks can borrow data that outlives the scope, since the returned future
+/// completes only after all of them have. They're polled by the scope itself, on whichever thread polls it,
+/// and dropping the scope drops them too.
+///
+/// `f` must return a boxed future, so that neither it nor the [`ScopedJoinHandle`]s it creates can escape
+/// the scope.
+///
+/// ```ignore
+/// let urls = vec!["/a", "/b", "/c"];
+/// let total = em_bindgen::task::scope(|s| {
+///     async move {
+///         let handles = urls.iter().map(|url| s.spawn(fetch_len(url))).collect::<Vec<_>>();
+///         futures::future::join_all(handles).await.into_iter().sum::<usize>()
+///     }
+///     .boxed_local()
+/// })
+/// .await;
+/// ```
+pub fn scope<'env, F, T>(f: F) -> ScopeFuture<'env, T>
+where
+    F: for<'scope> FnOnce(Scope<'scope, 'env>) -> LocalBoxFuture<'scope, T>,
+{
+    let shared = Rc::new(Shared {
+        spawned: RefCell::new(Vec::new()),
+        waker: RefCell::new(None),
+    });
+
+    return ScopeFuture {
+        body: Some(f(Scope {
+            shared: shared.clone(),
+            _phtm: PhantomData,
+        })),
+        output: None,
+        tasks: FuturesUnordered::new(),
+        shared,
+    };
+}
+
+/// A handle to spawn tasks that may borrow data for `'scope`, created by [`scope`].
+#[derive(Clone)]
+pub struct Scope<'scope, 'env: 'scope> {
+    shared: Rc<Shared<'scope>>,
+    _phtm: PhantomData<(&'scope mut &'scope (), &'env ())>,
+}
+
+struct Shared<'scope> {
+    spawned: RefCell<Vec<LocalBoxFuture<'scope, ()>>>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawns `fut` on the scope, returning a handle that resolves to its output.
+    pub fn spawn<Fut>(&self, fut: Fut) -> ScopedJoinHandle<'scope, Fut::Output>
+    where
+        Fut: 'scope + Future,
+        Fut::Output: 'scope,
+    {
+        let (send, recv) = oneshot::channel();
+        self.shared.spawned.borrow_mut().push(
+            async move {
+                let _ = send.send(fut.await);
+            }
+            .boxed_local(),
+        );
+
+        if let Some(waker) = self.shared.waker.borrow_mut().take() {
+            waker.wake();
+        }
+        return ScopedJoinHandle {
+            recv,
+            _phtm: PhantomData,
+        };
+    }
+}
+
+impl Debug for Scope<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scope").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`scope`].
+pub struct ScopeFuture<'env, T> {
+    body: Option<LocalBoxFuture<'env, T>>,
+    output: Option<T>,
+    tasks: FuturesUnordered<LocalBoxFuture<'env, ()>>,
+    shared: Rc<Shared<'env>>,
+}
+
+impl<'env, T> Future for ScopeFuture<'env, T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        *this.shared.waker.borrow_mut() = Some(cx.waker().clone());
+
+        if let Some(body) = this.body.as_mut() {
+            if let Poll::Ready(output) = body.poll_unpin(cx) {
+                this.body = None;
+                this.output = Some(output);
+            }
+        }
+
+        // Tasks may spawn more tasks while being polled, so we keep going until no new ones appear
+        loop {
+            this.tasks
+                .extend(this.shared.spawned.borrow_mut().drain(..));
+            while let Poll::Ready(Some(())) = this.tasks.poll_next_unpin(cx) {}
+
+            if this.shared.spawned.borrow().is_empty() {
+                break;
+            }
+        }
+
+        if this.tasks.is_empty() {
+            if let Some(output) = this.output.take() {
+                return Poll::Ready(output);
+            }
+        }
+        return Poll::Pending;
+    }
+}
+
+impl<T> Drop for ScopeFuture<'_, T> {
+    fn drop(&mut self) {
+        // Tasks that haven't been polled yet may hold a `Scope`, and so keep `shared` alive forever.
+        // They're taken out before being dropped, since dropping them may spawn more.
+        loop {
+            let spawned = std::mem::take(&mut *self.shared.spawned.borrow_mut());
+            if spawned.is_empty() {
+                break;
+            }
+            drop(spawned);
+        }
+        self.shared.waker.borrow_mut().take();
+    }
+}
+
+/// A handle to a task spawned with [`Scope::spawn`], which resolves to its output.
+///
+/// It can't outlive the scope, which owns the task.
+#[derive(Debug)]
+pub struct ScopedJoinHandle<'scope, T> {
+    recv: oneshot::Receiver<T>,
+    _phtm: PhantomData<&'scope mut &'scope ()>,
+}
+
+impl<T> Future for ScopedJoinHandle<'_, T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.recv.poll_unpin(cx).map(|res| {
+            // The task can only be dropped alongside its scope, which the handle can't outlive
+            res.expect("scoped task was dropped before completing")
+        });
+    }
+}
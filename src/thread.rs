@@ -0,0 +1,475 @@
+use crate::{
+    future::{spawn_on, JoinHandle},
+    proxying::{insert_registered, proxy_async, unregister_current, unregister_on_exit, Queue},
+    sys::{self, PthreadWrapper},
+};
+use std::{
+    ffi::{c_void, CStr, CString},
+    fmt::Debug,
+    future::Future,
+    io,
+    mem::{ManuallyDrop, MaybeUninit},
+    ops::Deref,
+    os::unix::thread::{JoinHandleExt, RawPthread},
+    sync::Arc,
+};
+
+/// A value that can only be accessed from the thread that created it, but can be shared between threads.
+///
+/// JavaScript values (like [`JsValue`](crate::value::JsValue)) are only valid on the thread that
+/// created them. Wrapping them in a [`ThreadBound`] allows storing them in shared state, while
+/// [`with`](ThreadBound::with) proxies any access to the owning thread.
+///
+/// If dropped from another thread, the value is sent back to the owning thread to be dropped there, or
+/// leaked if that thread has already exited (unless created with [`new_send`](ThreadBound::new_send)).
+pub struct ThreadBound<T> {
+    value: ManuallyDrop<T>,
+    thread: PthreadWrapper,
+    /// Drops the value on any thread, only set if `T` is [`Send`]
+    drop_anywhere: Option<unsafe fn(*mut ())>,
+}
+
+impl<T> ThreadBound<T> {
+    /// Binds `val` to the current thread.
+    #[inline]
+    pub fn new(val: T) -> Self {
+        return Self {
+            value: ManuallyDrop::new(val),
+            thread: PthreadWrapper::current(),
+            drop_anywhere: None,
+        };
+    }
+
+    /// Binds `val` to the current thread, like [`new`](ThreadBound::new).
+    ///
+    /// If the value is dropped from another thread after the owning thread has exited, it's dropped there
+    /// instead of being leaked.
+    #[inline]
+    pub fn new_send(val: T) -> Self
+    where
+        T: Send,
+    {
+        return Self {
+            drop_anywhere: Some(drop_boxed::<T>),
+            ..Self::new(val)
+        };
+    }
+
+    /// Returns `true` if the current thread is the one owning the value.
+    #[inline]
+    pub fn is_owner(&self) -> bool {
+        self.thread.is_current()
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.is_owner().then(|| &*self.value)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        match self.is_owner() {
+            true => Some(&mut *self.value),
+            false => None,
+        }
+    }
+
+    /// Returns the inner value, or `self` back if the current thread doesn't own it.
+    pub fn into_inner(self) -> Result<T, Self> {
+        if !self.is_owner() {
+            return Err(self);
+        }
+
+        let mut this = ManuallyDrop::new(self);
+        return Ok(unsafe { ManuallyDrop::take(&mut this.value) });
+    }
+
+    /// Runs `f` with the value on its owning thread, blocking the current thread until it completes.
+    ///
+    /// Returns `None` if the owning thread couldn't run `f` (for example, because it has exited).
+    pub fn with<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R + Send,
+        R: Send,
+    {
+        let value = AssertSend(&*self.value as *const T);
+        return Queue::system().proxy_blocking(&self.thread, move || {
+            let value = value;
+            f(unsafe { &*value.0 })
+        });
+    }
+
+    /// Runs `f` with the value on its owning thread, without blocking the current one.
+    ///
+    /// Resolves to `None` if the owning thread couldn't run `f` (for example, because it has exited).
+    pub fn with_async<F, R>(self: &Arc<Self>, f: F) -> impl 'static + Future<Output = Option<R>>
+    where
+        T: 'static,
+        F: 'static + FnOnce(&T) -> R + Send,
+        R: 'static + Send,
+    {
+        let this = self.clone();
+        return proxy_async(&self.thread, move || f(&*this.value));
+    }
+
+    /// Returns the thread owning the value.
+    #[inline]
+    pub fn owner(&self) -> RawPthread {
+        self.thread.0
+    }
+}
+
+impl<T> Drop for ThreadBound<T> {
+    fn drop(&mut self) {
+        if self.is_owner() {
+            return unsafe { ManuallyDrop::drop(&mut self.value) };
+        }
+
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        let ptr = Box::into_raw(Box::new(value)).cast::<()>();
+        let value = AssertSend(ptr);
+        let drop_fn: unsafe fn(*mut ()) = drop_boxed::<T>;
+        if Queue::system().proxy(&self.thread, move || {
+            let value = value;
+            unsafe { drop_fn(value.0) }
+        }) {
+            return;
+        }
+
+        // The owning thread has exited, and the closure was dropped without running, so we still own the value.
+        // SAFETY: `drop_anywhere` is only set if `T` is `Send`, so it may be dropped on this thread.
+        if let Some(drop_anywhere) = self.drop_anywhere {
+            unsafe { drop_anywhere(ptr) }
+        }
+    }
+}
+
+unsafe fn drop_boxed<T>(ptr: *mut ()) {
+    drop(Box::from_raw(ptr.cast::<T>()));
+}
+
+impl<T: Debug> Debug for ThreadBound<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("ThreadBound").field(value).finish(),
+            None => f.debug_tuple("ThreadBound").finish_non_exhaustive(),
+        }
+    }
+}
+
+// SAFETY: the value is only ever accessed (and dropped) on its owning thread: other threads go through
+// `with`, which proxies to it. If the owning thread has exited, the value is leaked rather than dropped
+// elsewhere, unless `T` is `Send`.
+unsafe impl<T> Send for ThreadBound<T> {}
+unsafe impl<T> Sync for ThreadBound<T> {}
+
+/// A [`ThreadBound`] value owned by the main runtime thread, where the DOM lives.
+pub struct MainThreadBound<T> {
+    inner: ThreadBound<T>,
+}
+
+impl<T> MainThreadBound<T> {
+    /// Binds `val` to the main runtime thread.
+    ///
+    /// # Panics
+    /// Panics if called from any thread other than the main runtime thread.
+    #[inline]
+    pub fn new(val: T) -> Self {
+        return match Self::try_new(val) {
+            Ok(this) => this,
+            Err(_) => panic!("MainThreadBound must be created on the main runtime thread"),
+        };
+    }
+
+    /// Binds `val` to the main runtime thread, returning it back if called from any other thread.
+    pub fn try_new(val: T) -> Result<Self, T> {
+        if !PthreadWrapper::main().is_current() {
+            return Err(val);
+        }
+
+        return Ok(Self {
+            inner: ThreadBound::new(val),
+        });
+    }
+
+    /// Runs `f` with the value on the main runtime thread, without blocking the current one.
+    ///
+    /// Resolves to `None` if the main runtime thread couldn't run `f`.
+    pub fn with_async<F, R>(self: &Arc<Self>, f: F) -> impl 'static + Future<Output = Option<R>>
+    where
+        T: 'static,
+        F: 'static + FnOnce(&T) -> R + Send,
+        R: 'static + Send,
+    {
+        let this = self.clone();
+        return proxy_async(&self.inner.thread, move || f(&*this.inner.value));
+    }
+
+    #[inline]
+    pub fn into_thread_bound(self) -> ThreadBound<T> {
+        self.inner
+    }
+}
+
+impl<T> Deref for MainThreadBound<T> {
+    type Target = ThreadBound<T>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T: Debug> Debug for MainThreadBound<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("MainThreadBound").field(value).finish(),
+            None => f.debug_tuple("MainThreadBound").finish_non_exhaustive(),
+        }
+    }
+}
+
+struct AssertSend<T>(T);
+unsafe impl<T> Send for AssertSend<T> {}
+
+/// Spawns a pthread that keeps running its event loop, so that it can host a local runtime.
+///
+/// The thread never exits on its own, so it (and its registered name) lives until [`RuntimeThread::exit`] is
+/// called. Futures can be spawned onto its runtime, and closures run on it, through the returned [`RuntimeThread`].
+pub struct Builder {
+    name: Option<Arc<str>>,
+    stack_size: Option<usize>,
+    canvases: Vec<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        return Self {
+            name: None,
+            stack_size: None,
+            canvases: Vec::new(),
+        };
+    }
+
+    /// Names the thread, registering it for [`proxying::lookup`](crate::proxying::lookup) under that name.
+    pub fn name(&mut self, name: impl Into<Arc<str>>) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn stack_size(&mut self, size: usize) -> &mut Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Transfers control of the canvas matching `selector` to the thread, as an `OffscreenCanvas`.
+    ///
+    /// This requires linking with `-sOFFSCREENCANVAS_SUPPORT`.
+    #[doc(alias = "emscripten_pthread_attr_settransferredcanvases")]
+    pub fn transfer_canvas(&mut self, selector: impl Into<String>) -> &mut Self {
+        self.canvases.push(selector.into());
+        self
+    }
+
+    /// Spawns the thread and returns a handle to it.
+    ///
+    /// Note that Emscripten can only start a new pthread once the calling thread yields back to the
+    /// JavaScript event loop, unless it's already available in the pthread pool (see `PTHREAD_POOL_SIZE`).
+    pub fn spawn(&self) -> io::Result<RuntimeThread> {
+        extern "C" fn start(arg: *mut c_void) -> *mut c_void {
+            let name = unsafe { Box::from_raw(arg.cast::<Option<Arc<str>>>()) };
+            if let Some(name) = *name {
+                if let Ok(c_name) = CString::new(&*name) {
+                    unsafe {
+                        sys::emscripten_set_thread_name(libc::pthread_self(), c_name.as_ptr())
+                    };
+                }
+                unregister_on_exit(name);
+            }
+
+            // Returns to the event loop without exiting the thread, so that it keeps running its tasks
+            unsafe { sys::emscripten_exit_with_live_runtime() }
+        }
+
+        let canvases = match self.canvases.is_empty() {
+            true => None,
+            false => Some(
+                CString::new(self.canvases.join(","))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            ),
+        };
+
+        let arg = Box::into_raw(Box::new(self.name.clone()));
+        let thread = match unsafe {
+            create_detached(self.stack_size, canvases.as_deref(), start, arg.cast())
+        } {
+            Ok(thread) => thread,
+            Err(e) => {
+                drop(unsafe { Box::from_raw(arg) });
+                return Err(e);
+            }
+        };
+
+        if let Some(ref name) = self.name {
+            insert_registered(name.clone(), thread, None);
+        }
+
+        return Ok(RuntimeThread {
+            name: self.name.clone(),
+            thread,
+        });
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A handle to a thread spawned by [`Builder`], that can spawn futures and run closures on it from any thread.
+#[derive(Debug, Clone)]
+pub struct RuntimeThread {
+    name: Option<Arc<str>>,
+    thread: PthreadWrapper,
+}
+
+impl RuntimeThread {
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Spawns `fut` onto the thread's local runtime.
+    #[inline]
+    pub fn spawn<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Send + Future,
+        Fut::Output: 'static + Send,
+    {
+        spawn_on(&self.thread, move || fut)
+    }
+
+    /// Spawns the future returned by `f` onto the thread's local runtime, like [`spawn_on`].
+    #[inline]
+    pub fn spawn_with<F, Fut>(&self, f: F) -> JoinHandle<Fut::Output>
+    where
+        F: 'static + Send + FnOnce() -> Fut,
+        Fut: 'static + Future,
+        Fut::Output: 'static + Send,
+    {
+        spawn_on(&self.thread, f)
+    }
+
+    /// Runs `f` on the thread, blocking the current thread until it completes.
+    ///
+    /// Returns `None` if the thread couldn't run `f`.
+    #[doc(alias = "emscripten_proxy_sync")]
+    #[inline]
+    pub fn run<F, T>(&self, f: F) -> Option<T>
+    where
+        F: FnOnce() -> T + Send,
+        T: Send,
+    {
+        Queue::system().proxy_blocking(&self.thread, f)
+    }
+
+    /// Runs `f` on the thread, without blocking the current one.
+    ///
+    /// Resolves to `None` if the thread couldn't run `f`.
+    #[inline]
+    pub fn run_async<F, T>(&self, f: F) -> impl 'static + Future<Output = Option<T>>
+    where
+        F: 'static + FnOnce() -> T + Send,
+        T: 'static + Send,
+    {
+        proxy_async(&self.thread, f)
+    }
+
+    /// Exits the thread once it's done with the work proxied to it so far, dropping the tasks still on its
+    /// runtime and unregistering its name.
+    ///
+    /// Returns `false` if the thread couldn't be reached, because it had already exited.
+    #[doc(alias = "pthread_exit")]
+    pub fn exit(&self) -> bool {
+        if self.thread.is_current() {
+            exit_current();
+        }
+        return Queue::system().proxy(&self.thread, || exit_current());
+    }
+}
+
+fn exit_current() -> ! {
+    unregister_current();
+    unsafe { libc::pthread_exit(std::ptr::null_mut()) }
+}
+
+impl JoinHandleExt for RuntimeThread {
+    #[inline]
+    fn as_pthread_t(&self) -> RawPthread {
+        self.thread.0
+    }
+
+    #[inline]
+    fn into_pthread_t(self) -> RawPthread {
+        self.thread.0
+    }
+}
+
+/// Destroys the thread attributes when dropped.
+struct Attr(libc::pthread_attr_t);
+
+impl Drop for Attr {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { libc::pthread_attr_destroy(&mut self.0) };
+    }
+}
+
+/// Starts a detached pthread running `start(arg)`.
+///
+/// # Safety
+/// `start` must be safe to call with `arg` on the new thread. If an error is returned, the thread was
+/// never started, and the caller still owns `arg`.
+pub(crate) unsafe fn create_detached(
+    stack_size: Option<usize>,
+    canvases: Option<&CStr>,
+    start: extern "C" fn(*mut c_void) -> *mut c_void,
+    arg: *mut c_void,
+) -> io::Result<PthreadWrapper> {
+    let mut attr = MaybeUninit::<libc::pthread_attr_t>::uninit();
+    check(libc::pthread_attr_init(attr.as_mut_ptr()))?;
+    let mut attr = Attr(attr.assume_init());
+
+    check(libc::pthread_attr_setdetachstate(
+        &mut attr.0,
+        libc::PTHREAD_CREATE_DETACHED,
+    ))?;
+    if let Some(size) = stack_size {
+        check(libc::pthread_attr_setstacksize(&mut attr.0, size))?;
+    }
+    if let Some(canvases) = canvases {
+        check(sys::emscripten_pthread_attr_settransferredcanvases(
+            (&mut attr.0 as *mut libc::pthread_attr_t).cast(),
+            canvases.as_ptr(),
+        ))?;
+    }
+
+    let mut thread = MaybeUninit::<libc::pthread_t>::uninit();
+    check(libc::pthread_create(
+        thread.as_mut_ptr(),
+        &attr.0,
+        start,
+        arg,
+    ))?;
+    return Ok(PthreadWrapper(thread.assume_init()));
+}
+
+#[inline]
+fn check(ret: i32) -> io::Result<()> {
+    match ret {
+        0 => Ok(()),
+        e => Err(io::Error::from_raw_os_error(e)),
+    }
+}
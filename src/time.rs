@@ -0,0 +1,409 @@
+use crate::{
+    get_now,
+    sys::{self, PthreadWrapper},
+};
+use futures::{task::AtomicWaker, Stream};
+use pin_project::pin_project;
+use std::{
+    ffi::{c_long, c_void},
+    future::Future,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+/// A measurement of a monotonically nondecreasing clock, based on [`get_now`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant {
+    /// Time elapsed since the clock's origin
+    inner: Duration,
+}
+
+impl Instant {
+    #[doc(alias = "emscripten_get_now")]
+    #[inline]
+    pub fn now() -> Self {
+        return Self {
+            inner: Duration::from_secs_f64(get_now().max(0.) / 1000.),
+        };
+    }
+
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        Self::now().saturating_duration_since(*self)
+    }
+
+    /// # Panics
+    /// Panics if `earlier` is later than `self`.
+    #[inline]
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .expect("supplied instant is later than self")
+    }
+
+    #[inline]
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.inner.checked_sub(earlier.inner)
+    }
+
+    #[inline]
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.inner.saturating_sub(earlier.inner)
+    }
+
+    #[inline]
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.inner
+            .checked_add(duration)
+            .map(|inner| Instant { inner })
+    }
+
+    #[inline]
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.inner
+            .checked_sub(duration)
+            .map(|inner| Instant { inner })
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    /// Panics if the resulting instant overflows.
+    #[inline]
+    fn add(self, rhs: Duration) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    #[inline]
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    /// Panics if the resulting instant underflows.
+    #[inline]
+    fn sub(self, rhs: Duration) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Returns the amount of time elapsed from `rhs` to `self`, or zero if `rhs` is later than `self`.
+    #[inline]
+    fn sub(self, rhs: Instant) -> Self::Output {
+        self.saturating_duration_since(rhs)
+    }
+}
+
+/// Waits until `dur` has elapsed.
+#[inline]
+pub fn sleep(dur: Duration) -> Sleep {
+    sleep_until(Instant::now().checked_add(dur).unwrap_or(Instant {
+        inner: Duration::MAX,
+    }))
+}
+
+/// Waits until `deadline` is reached.
+#[inline]
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        timer: None,
+    }
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`].
+///
+/// The underlying JavaScript timer is cleared when the future is dropped.
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Instant,
+    timer: Option<Timer>,
+}
+
+impl Sleep {
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    #[inline]
+    pub fn is_elapsed(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Resets the future to complete at `deadline` instead, even if it had already completed.
+    #[inline]
+    pub fn reset(&mut self, deadline: Instant) {
+        self.timer = None;
+        self.deadline = deadline;
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let now = Instant::now();
+            if now >= self.deadline {
+                self.timer = None;
+                return Poll::Ready(());
+            }
+
+            if let Some(ref timer) = self.timer {
+                timer.shared.waker.register(cx.waker());
+                if !timer.shared.fired.load(Ordering::Acquire) {
+                    return Poll::Pending;
+                }
+                // The timer fired early (slightly, or because its delay was clamped), so we need a new one.
+            }
+
+            self.timer = Some(Timer::new(self.deadline - now));
+        }
+    }
+}
+
+/// Requires `fut` to complete before `dur` has elapsed.
+#[inline]
+pub fn timeout<Fut: Future>(dur: Duration, fut: Fut) -> Timeout<Fut> {
+    Timeout {
+        fut,
+        sleep: sleep(dur),
+    }
+}
+
+/// Requires `fut` to complete before `deadline` is reached.
+#[inline]
+pub fn timeout_at<Fut: Future>(deadline: Instant, fut: Fut) -> Timeout<Fut> {
+    Timeout {
+        fut,
+        sleep: sleep_until(deadline),
+    }
+}
+
+/// Future returned by [`timeout`] and [`timeout_at`].
+#[pin_project]
+#[derive(Debug)]
+pub struct Timeout<Fut> {
+    #[pin]
+    fut: Fut,
+    sleep: Sleep,
+}
+
+impl<Fut> Timeout<Fut> {
+    #[inline]
+    pub fn get_ref(&self) -> &Fut {
+        &self.fut
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Fut {
+        self.fut
+    }
+}
+
+impl<Fut: Future> Future for Timeout<Fut> {
+    type Output = Result<Fut::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(val) = this.fut.poll(cx) {
+            return Poll::Ready(Ok(val));
+        }
+
+        ready!(Pin::new(this.sleep).poll(cx));
+        return Poll::Ready(Err(Elapsed));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("The deadline has elapsed")]
+pub struct Elapsed;
+
+/// Creates a stream that yields every `period`, with the first tick completing immediately.
+///
+/// # Panics
+/// Panics if `period` is zero.
+#[inline]
+pub fn interval(period: Duration) -> Interval {
+    interval_at(Instant::now(), period)
+}
+
+/// Creates a stream that yields every `period`, with the first tick completing at `start`.
+///
+/// # Panics
+/// Panics if `period` is zero.
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    assert!(!period.is_zero(), "interval period must be non-zero");
+    return Interval {
+        sleep: sleep_until(start),
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+    };
+}
+
+/// Defines what an [`Interval`] does when ticks are missed, because it wasn't polled in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MissedTickBehavior {
+    /// Fires the missed ticks as fast as possible, until the interval catches up.
+    #[default]
+    Burst,
+    /// Delays every future tick, so that they're `period` apart from the moment the late tick completed.
+    Delay,
+    /// Skips the missed ticks, and keeps firing on multiples of `period` from the start.
+    Skip,
+}
+
+/// Stream returned by [`interval`] and [`interval_at`].
+#[derive(Debug)]
+pub struct Interval {
+    sleep: Sleep,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+}
+
+impl Interval {
+    /// Completes when the next tick is reached, returning its scheduled instant.
+    #[inline]
+    pub async fn tick(&mut self) -> Instant {
+        std::future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        ready!(Pin::new(&mut self.sleep).poll(cx));
+
+        let deadline = self.sleep.deadline();
+        let now = Instant::now();
+        let next = match deadline.checked_add(self.period) {
+            Some(next) if next > now => next,
+            _ => match self.missed_tick_behavior {
+                MissedTickBehavior::Burst => deadline + self.period,
+                MissedTickBehavior::Delay => now + self.period,
+                MissedTickBehavior::Skip => {
+                    let missed = (now - deadline).as_nanos() / self.period.as_nanos();
+                    let missed = u32::try_from(missed + 1).unwrap_or(u32::MAX);
+                    deadline + self.period * missed
+                }
+            },
+        };
+
+        self.sleep.reset(next);
+        return Poll::Ready(deadline);
+    }
+
+    /// Resets the interval, so that the next tick completes one `period` from now.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.sleep.reset(Instant::now() + self.period);
+    }
+
+    #[inline]
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    #[inline]
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
+
+    #[inline]
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
+
+/// Longest delay `setTimeout` supports, since it stores it as an `int32`. Longer delays fire immediately.
+const MAX_TIMEOUT_MS: f64 = i32::MAX as f64;
+
+/// A JavaScript timer, cleared when dropped.
+///
+/// Delays over [`MAX_TIMEOUT_MS`] are clamped, so the timer fires early and its owner re-arms it for the
+/// remaining time.
+#[derive(Debug)]
+struct Timer {
+    id: c_long,
+    thread: PthreadWrapper,
+    shared: Arc<TimerShared>,
+}
+
+#[derive(Debug)]
+struct TimerShared {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Timer {
+    fn new(dur: Duration) -> Self {
+        unsafe extern "C" fn fire(arg: *mut c_void) {
+            let shared = Arc::from_raw(arg.cast_const().cast::<TimerShared>());
+            shared.fired.store(true, Ordering::Release);
+            shared.waker.wake();
+        }
+
+        let shared = Arc::new(TimerShared {
+            fired: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+
+        let id = unsafe {
+            sys::emscripten_set_timeout(
+                Some(fire),
+                f64::min(dur.as_secs_f64() * 1000., MAX_TIMEOUT_MS),
+                Arc::into_raw(shared.clone()).cast_mut().cast(),
+            )
+        };
+
+        return Self {
+            id,
+            thread: PthreadWrapper::current(),
+            shared,
+        };
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        // Timers can only be cleared from the thread that set them. Otherwise, we just let them fire.
+        if self.thread.is_current() && !self.shared.fired.load(Ordering::Acquire) {
+            unsafe {
+                sys::emscripten_clear_timeout(self.id);
+                Arc::decrement_strong_count(Arc::as_ptr(&self.shared));
+            }
+        }
+    }
+}
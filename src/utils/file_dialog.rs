@@ -1,3 +1,4 @@
+use crate::future::{event, Event};
 use alloc::{
     alloc::Layout,
     borrow::Cow,
@@ -12,7 +13,6 @@ use libstd::{
     time::SystemTime,
 };
 use typed_arena::Arena;
-use utils_atomics::channel::once::{async_channel, AsyncSender};
 
 #[derive(Debug, Clone, Default)]
 pub struct FileDialog {
@@ -38,8 +38,8 @@ impl FileDialog {
 impl FileDialog {
     pub async fn load_file(self) -> Option<FileHandle> {
         unsafe extern "C" fn oncomplete(file: *mut sys::File, user_data: *mut c_void) {
-            let send = Box::from_raw(user_data.cast::<AsyncSender<Option<sys::File>>>());
-            send.send(NonNull::new(file).map(|x| x.read()));
+            let event = Box::from_raw(user_data.cast::<Event<Option<sys::File>>>());
+            event.fulfill(NonNull::new(file).map(|x| x.read()));
         }
 
         let accept = match self
@@ -59,15 +59,15 @@ impl FileDialog {
         };
 
         unsafe {
-            let (send, recv) = async_channel::<Option<sys::File>>();
+            let (event, promise) = event::<Option<sys::File>>();
             sys::LoadFile(
                 accept.as_ptr(),
                 Some(memalloc),
                 Some(oncomplete),
-                Box::into_raw(Box::new(send)).cast(),
+                Box::into_raw(Box::new(event)).cast(),
             );
 
-            let file = recv.await.flatten()?;
+            let file = promise.await.unwrap_or_else(|e| match e {})?;
             if file.contents.is_null() {
                 return None;
             }